@@ -0,0 +1,176 @@
+//! Compares the spatial grid broad phase against checking every pair of entities,
+//! then times the engine updating crowds of entities that move, collide and look
+//! around. Run with `cargo bench --features bench`.
+
+use game::{
+    entity::{Collider, ColliderKind, Effect, Entity, EntityId, Input, Rotation, Sprite, Update},
+    spatial::{Bounds, SpatialGrid},
+    steering::{separation, Wander},
+    world::World,
+    Bench,
+};
+use std::{
+    hint::black_box,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+const COUNTS: [usize; 6] = [100, 500, 1_000, 2_000, 5_000, 10_000];
+const TICKS: u32 = 10;
+
+// roughly a sprite's collision box, in canvas cells
+const BOX_WIDTH: i32 = 12;
+const BOX_HEIGHT: i32 = 7;
+
+const CROWD_TAG: &str = "crowd";
+
+/// Side of the square area `count` things are scattered over, so density stays the same.
+fn side(count: usize) -> u64 {
+    ((count as f64).sqrt() * 30.0) as u64
+}
+
+/// Points scattered over a `side` by `side` square.
+fn points(count: usize, side: u64) -> Vec<(i32, i32)> {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        // xorshift
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % side) as i32
+    };
+
+    (0..count).map(|_| (next(), next())).collect()
+}
+
+fn scatter(count: usize) -> Vec<Bounds> {
+    points(count, side(count))
+        .into_iter()
+        .map(|(x, y)| Bounds::new(x, y, x + BOX_WIDTH, y + BOX_HEIGHT))
+        .collect()
+}
+
+/// Wanders around keeping its distance from the rest of the crowd, like an enemy
+/// looking for the player.
+#[derive(Debug)]
+struct Walker {
+    sprite: Rc<Sprite>,
+    start: (f32, f32),
+    wander: Wander,
+}
+
+impl Entity for Walker {
+    fn start_pos(&self) -> (f32, f32) {
+        self.start
+    }
+
+    fn sprite(&self) -> &Rc<Sprite> {
+        &self.sprite
+    }
+
+    fn update(&mut self, _input: Input, world: &mut World) -> Update {
+        black_box(world.nearest_tagged(CROWD_TAG));
+        let step = self.wander.update(world, 0.5)
+            + separation(world, 8.0, 0.5, |entity| entity.has_tag(CROWD_TAG));
+
+        Update::Action {
+            step,
+            rotate: Rotation::Zero,
+        }
+    }
+
+    fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+    fn effect(&mut self, _effect: Effect) {}
+
+    fn collider(&self) -> Collider {
+        Collider::new(1, 1, ColliderKind::Solid)
+    }
+
+    fn tags(&self) -> &[&'static str] {
+        &[CROWD_TAG]
+    }
+}
+
+fn crowd(count: usize) -> Bench {
+    let side = side(count);
+    let sprite = Rc::new(Sprite::filled(7, 8, (255, 255, 255)));
+    let walkers = points(count, side).into_iter().map(|(x, y)| {
+        Box::new(Walker {
+            sprite: sprite.clone(),
+            start: (x as f32 / side as f32, y as f32 / side as f32),
+            wander: Wander::new(0.5),
+        }) as Box<dyn Entity>
+    });
+
+    let side = side.min(u16::MAX as u64) as u16;
+    Bench::new(walkers, side, side).expect("crowd fits in the canvas")
+}
+
+fn naive(boxes: &[Bounds]) -> usize {
+    let mut pairs = 0;
+    for (index, a) in boxes.iter().enumerate() {
+        for (other_index, b) in boxes.iter().enumerate() {
+            if index != other_index && a.overlaps(b) {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+fn grid(grid: &mut SpatialGrid, boxes: &[Bounds]) -> usize {
+    // rebuilt every tick, like the engine does
+    grid.clear();
+    for (index, bounds) in boxes.iter().enumerate() {
+        grid.insert(index, *bounds);
+    }
+
+    let mut pairs = 0;
+    for (index, a) in boxes.iter().enumerate() {
+        for other_index in grid.query(*a) {
+            if index != other_index && a.overlaps(&boxes[other_index]) {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut pairs = 0;
+    for _ in 0..TICKS {
+        pairs = black_box(f());
+    }
+    (start.elapsed() / TICKS, pairs)
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>14} {:>14} {:>10}",
+        "entities", "naive/tick", "grid/tick", "pairs"
+    );
+
+    let mut spatial_grid = SpatialGrid::default();
+    for count in COUNTS {
+        let boxes = scatter(count);
+        let (naive_time, naive_pairs) = time(|| naive(&boxes));
+        let (grid_time, grid_pairs) = time(|| grid(&mut spatial_grid, &boxes));
+        assert_eq!(naive_pairs, grid_pairs, "broad phase missed a collision");
+
+        println!("{count:>8} {naive_time:>14.2?} {grid_time:>14.2?} {grid_pairs:>10}");
+    }
+
+    println!();
+    println!("{:>8} {:>14}", "entities", "engine/tick");
+    for count in COUNTS {
+        let mut bench = crowd(count);
+        let (engine_time, _) = time(|| {
+            bench.tick().expect("tick succeeds");
+            bench.len()
+        });
+
+        println!("{count:>8} {engine_time:>14.2?}");
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+
+use crate::{
+    entity::{Rotation, Update, Vector},
+    world::World,
+};
+
+/// A scripted behavior, e.g. "move 5 steps, wait, turn, shoot", built from smaller
+/// actions. An entity keeps one and calls `update` from its own `update` to get
+/// this tick's movement.
+///
+/// Actions that take no time, like turning or running a closure, carry straight on
+/// into the next action of a sequence in the same tick.
+#[derive(Clone, Debug)]
+pub struct Action(Kind);
+
+type Run = Rc<dyn Fn(&mut World)>;
+
+#[derive(Clone)]
+enum Kind {
+    MoveBy {
+        step: Vector,
+        ticks: u32,
+    },
+    Wait(u32),
+    Rotate(Option<Rotation>),
+    Run(Option<Run>),
+    Sequence(VecDeque<Action>),
+    Parallel(Vec<Action>),
+    Repeat {
+        action: Box<Action>,
+        current: Box<Action>,
+        times: Option<u32>,
+    },
+}
+
+impl Debug for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::MoveBy { step, ticks } => f
+                .debug_struct("MoveBy")
+                .field("step", step)
+                .field("ticks", ticks)
+                .finish(),
+            Kind::Wait(ticks) => f.debug_tuple("Wait").field(ticks).finish(),
+            Kind::Rotate(rotation) => f.debug_tuple("Rotate").field(rotation).finish(),
+            Kind::Run(_) => f.debug_tuple("Run").finish_non_exhaustive(),
+            Kind::Sequence(actions) => f.debug_tuple("Sequence").field(actions).finish(),
+            Kind::Parallel(actions) => f.debug_tuple("Parallel").field(actions).finish(),
+            Kind::Repeat { current, times, .. } => f
+                .debug_struct("Repeat")
+                .field("current", current)
+                .field("times", times)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl Action {
+    /// Move by `step` every tick, for `ticks` ticks.
+    pub fn move_by(step: Vector, ticks: u32) -> Self {
+        Self(Kind::MoveBy { step, ticks })
+    }
+
+    pub fn wait(ticks: u32) -> Self {
+        Self(Kind::Wait(ticks))
+    }
+
+    pub fn rotate(rotation: Rotation) -> Self {
+        Self(Kind::Rotate(Some(rotation)))
+    }
+
+    /// Do something with the world, e.g. spawn a bullet.
+    pub fn run<F>(f: F) -> Self
+    where
+        F: Fn(&mut World) + 'static,
+    {
+        Self(Kind::Run(Some(Rc::new(f))))
+    }
+
+    /// Each action in turn.
+    pub fn sequence<T>(actions: T) -> Self
+    where
+        T: IntoIterator<Item = Action>,
+    {
+        Self(Kind::Sequence(actions.into_iter().collect()))
+    }
+
+    /// Every action at once, until they've all finished. Their movements add up.
+    pub fn parallel<T>(actions: T) -> Self
+    where
+        T: IntoIterator<Item = Action>,
+    {
+        Self(Kind::Parallel(actions.into_iter().collect()))
+    }
+
+    /// The action over again, `times` times.
+    /// Each repeat starts on a new tick, so even actions that take no time can't hang.
+    pub fn repeat(action: Action, times: u32) -> Self {
+        Self::repeating(action, Some(times))
+    }
+
+    /// The action over again, forever.
+    pub fn forever(action: Action) -> Self {
+        Self::repeating(action, None)
+    }
+
+    fn repeating(action: Action, times: Option<u32>) -> Self {
+        Self(Kind::Repeat {
+            current: Box::new(action.clone()),
+            action: Box::new(action),
+            times,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.0 {
+            Kind::MoveBy { ticks, .. } | Kind::Wait(ticks) => *ticks == 0,
+            Kind::Rotate(rotation) => rotation.is_none(),
+            Kind::Run(f) => f.is_none(),
+            Kind::Sequence(actions) => actions.is_empty(),
+            Kind::Parallel(actions) => actions.iter().all(Action::is_finished),
+            Kind::Repeat { times, .. } => *times == Some(0),
+        }
+    }
+
+    /// Whether the action would finish without using up a tick.
+    fn is_instant(&self) -> bool {
+        match &self.0 {
+            Kind::MoveBy { ticks, .. } | Kind::Wait(ticks) => *ticks == 0,
+            Kind::Rotate(_) | Kind::Run(_) => true,
+            Kind::Sequence(actions) => actions.iter().all(Action::is_instant),
+            Kind::Parallel(actions) => actions.iter().all(Action::is_instant),
+            Kind::Repeat { .. } => false,
+        }
+    }
+
+    /// Run the action for a tick, returning this tick's movement.
+    /// Finished actions don't move.
+    pub fn update(&mut self, world: &mut World) -> Update {
+        let (mut step, mut rotate) = (Vector::default(), Rotation::Zero);
+        self.advance(world, &mut step, &mut rotate);
+
+        Update::Action { step, rotate }
+    }
+
+    /// Run the action for a tick, adding its movement to `step` and `rotate`.
+    /// Returns whether it's finished.
+    fn advance(&mut self, world: &mut World, step: &mut Vector, rotate: &mut Rotation) -> bool {
+        match &mut self.0 {
+            Kind::MoveBy {
+                step: move_step,
+                ticks,
+            } => {
+                if *ticks > 0 {
+                    *step = *step + *move_step;
+                    *ticks -= 1;
+                }
+            }
+
+            Kind::Wait(ticks) => *ticks = ticks.saturating_sub(1),
+
+            Kind::Rotate(rotation) => {
+                if let Some(rotation) = rotation.take() {
+                    *rotate += rotation;
+                }
+            }
+
+            Kind::Run(f) => {
+                if let Some(f) = f.take() {
+                    f(world);
+                }
+            }
+
+            Kind::Sequence(actions) => {
+                while let Some(action) = actions.front_mut() {
+                    let instant = action.is_instant();
+                    if !action.advance(world, step, rotate) {
+                        break;
+                    }
+
+                    actions.pop_front();
+                    // the next action starts next tick, unless this one took no time
+                    if !instant {
+                        break;
+                    }
+                }
+            }
+
+            Kind::Parallel(actions) => {
+                for action in actions.iter_mut() {
+                    if !action.is_finished() {
+                        action.advance(world, step, rotate);
+                    }
+                }
+            }
+
+            Kind::Repeat {
+                action,
+                current,
+                times,
+            } => {
+                if *times != Some(0) && current.advance(world, step, rotate) {
+                    *times = times.map(|times| times - 1);
+                    *current = action.clone();
+                }
+            }
+        }
+
+        self.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Each tick's movement until the action finishes, giving up after `limit` ticks.
+    fn run(mut action: Action, limit: usize) -> Vec<(Vector, Rotation)> {
+        World::with_empty(|world| {
+            let mut ticks = vec![];
+            while !action.is_finished() && ticks.len() < limit {
+                match action.update(world) {
+                    Update::Action { step, rotate } => ticks.push((step, rotate)),
+                    update => panic!("unexpected update {update:?}"),
+                }
+            }
+            ticks
+        })
+    }
+
+    fn right(ticks: u32) -> Action {
+        Action::move_by(Vector::new(1.0, 0.0), ticks)
+    }
+
+    #[test]
+    fn sequences_run_each_action_in_turn() {
+        let ticks = run(Action::sequence([right(2), Action::wait(1), right(1)]), 10);
+
+        let steps: Vec<_> = ticks.iter().map(|(step, _)| step.x).collect();
+        assert_eq!(steps, [1.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn instant_actions_carry_on_into_the_next() {
+        let ran = Rc::new(Cell::new(0));
+        let counter = ran.clone();
+        let action = Action::sequence([
+            Action::rotate(Rotation::HalfPi),
+            Action::run(move |_| counter.set(counter.get() + 1)),
+            right(1),
+        ]);
+
+        let ticks = run(action, 10);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].0, Vector::new(1.0, 0.0));
+        assert!(matches!(ticks[0].1, Rotation::HalfPi));
+        assert_eq!(ran.get(), 1);
+    }
+
+    #[test]
+    fn repeats_run_the_action_again_a_tick_at_a_time() {
+        assert_eq!(run(Action::repeat(right(2), 3), 10).len(), 6);
+
+        // even instant actions take a tick each time round
+        let ran = Rc::new(Cell::new(0));
+        let counter = ran.clone();
+        let action = Action::repeat(Action::run(move |_| counter.set(counter.get() + 1)), 3);
+        assert_eq!(run(action, 10).len(), 3);
+        assert_eq!(ran.get(), 3);
+    }
+
+    #[test]
+    fn forever_never_finishes() {
+        assert_eq!(
+            run(Action::forever(Action::rotate(Rotation::HalfPi)), 5).len(),
+            5
+        );
+    }
+
+    #[test]
+    fn parallel_movements_add_up_until_all_finish() {
+        let up = Action::move_by(Vector::new(0.0, 1.0), 1);
+        let ticks = run(Action::parallel([right(2), up]), 10);
+
+        let steps: Vec<_> = ticks.into_iter().map(|(step, _)| step).collect();
+        assert_eq!(steps, [Vector::new(1.0, 1.0), Vector::new(1.0, 0.0)]);
+    }
+}
@@ -1,14 +1,30 @@
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use std::{
+    cell::Cell,
     fs::File,
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
 };
 use tracing::Level;
 
 use game::{
-    entity::{Effect, Entity, Input, Rotation, Sprite, Update, Vector},
+    behavior::Action,
+    draw::Draw,
+    entity::{
+        Boundary, Collider, ColliderKind, Effect, Entity, EntityId, Input, Label, Rotation, Sprite,
+        Update, Vector,
+    },
+    fsm::StateMachine,
+    hud::{Content, HudElement, Region},
+    physics::Body,
+    random::Rng,
+    scene::{Scene, SceneChange, Transition},
+    status::{Status, StatusKind},
+    steering,
+    timer::{Delay, Timer},
+    world::{Seen, World},
     Engine,
 };
 
@@ -23,6 +39,40 @@ const BMPS_DIR: &str = "bmps";
 const SMILEY_BMP: &str = "smiley.bmp";
 const MEANIE_BMP: &str = "meanie.bmp";
 
+const PLAYER_HEALTH: i32 = 10;
+const PLAYER_DAMAGE: i32 = 10;
+const ENEMY_HEALTH: i32 = 5;
+const LABEL_COLOR: (u8, u8, u8) = (255, 255, 255);
+const HEALTH_BAR_COLOR: (u8, u8, u8) = (200, 30, 30);
+const TEXT_COLOR: (u8, u8, u8) = (20, 20, 20);
+const BULLET_COLOR: (u8, u8, u8) = (240, 220, 60);
+
+const PLAYER_LAYER: u32 = 1 << 0;
+const ENEMY_LAYER: u32 = 1 << 1;
+const BULLET_LAYER: u32 = 1 << 2;
+
+const PLAYER_TAG: &str = "player";
+const ENEMY_TAG: &str = "enemy";
+const ENEMY_SIGHT: f32 = 25.0;
+const ENEMY_SPEED: f32 = 0.75;
+// about the size of an enemy, in pixels
+const ENEMY_PATH_CELL_SIZE: f32 = 10.0;
+const ENEMY_SPACING: f32 = 12.0;
+const ENEMY_PATROL_SPEED: f32 = 0.5;
+const ENEMY_PATROL_TICKS: u32 = 12;
+
+const BULLET_DAMAGE: i32 = 5;
+const BULLET_TICKS: u32 = 20;
+const BULLET_DRAG: f32 = 0.05;
+const BULLET_BLAST_RADIUS: f32 = 12.0;
+const BULLET_STUN_TICKS: u32 = 10;
+
+const RELOAD_TIMER: &str = "reload";
+const RELOAD_TICKS: u32 = 3;
+const WAVE_TIMER: &str = "wave";
+const WAVE_SECONDS: f32 = 15.0;
+const WAVE_HEIGHTS: Range<f32> = 0.2..0.8;
+
 const DEFAULT_FPS: u32 = 5;
 const DEFAULT_LOG: &str = "snake.log";
 
@@ -34,28 +84,71 @@ struct Cli {
 
     #[arg(long, default_value=DEFAULT_LOG)]
     log: PathBuf,
+
+    /// Seed for the random numbers, to play a run again exactly
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Record every tick's input to this file
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Play back a run recorded with --record
+    #[arg(long)]
+    replay: Option<PathBuf>,
 }
 
 #[derive(Debug)]
-struct Player((f32, f32), Rc<Sprite>, i32, Rotation);
+struct Player {
+    start: (f32, f32),
+    sprite: Rc<Sprite>,
+    // shared with the HUD
+    health: Rc<Cell<i32>>,
+    facing: Rotation,
+    // enemies run into last tick, damaged through the world so their statuses count
+    hits: Vec<EntityId>,
+}
 
 impl Entity for Player {
     fn start_pos(&self) -> (f32, f32) {
-        self.0
+        self.start
     }
 
-    fn update(&mut self, input: Input) -> Update {
-        if self.2 <= 0 {
+    fn tags(&self) -> &[&'static str] {
+        &[PLAYER_TAG]
+    }
+
+    fn collider(&self) -> Collider {
+        Collider::new(PLAYER_LAYER, ENEMY_LAYER, ColliderKind::Trigger)
+    }
+
+    fn boundary(&self) -> Boundary {
+        // like classic snake, leaving one side comes back in the other
+        Boundary::Wrap
+    }
+
+    fn update(&mut self, input: Input, world: &mut World) -> Update {
+        if self.health.get() <= 0 {
             return Update::Destroy;
         }
 
-        let forward = match self.3 {
-            Rotation::Zero => Vector::new(0, 2),
-            Rotation::HalfPi => Vector::new(-2, 0),
-            Rotation::Pi => Vector::new(0, -2),
-            Rotation::ThreeHalvesPi => Vector::new(2, 0),
+        for hit in self.hits.drain(..) {
+            world.send(hit, Effect::Damage(PLAYER_DAMAGE));
+        }
+
+        let forward = match self.facing {
+            Rotation::Zero => Vector::new(0.0, 2.0),
+            Rotation::HalfPi => Vector::new(-2.0, 0.0),
+            Rotation::Pi => Vector::new(0.0, -2.0),
+            Rotation::ThreeHalvesPi => Vector::new(2.0, 0.0),
         };
 
+        if input == Input::Action && !world.has_timer(RELOAD_TIMER) {
+            // fire from just in front of the player, clear of its own sprite
+            world.spawn(Box::new(Bullet::new(forward * 2.0)), forward * 4.0);
+            world.start_timer(Timer::after(RELOAD_TIMER, Delay::Ticks(RELOAD_TICKS)));
+        }
+
         Update::Action {
             step: match input {
                 Input::Up => forward,
@@ -63,11 +156,11 @@ impl Entity for Player {
             },
             rotate: match input {
                 Input::Left => {
-                    self.3 += Rotation::HalfPi;
+                    self.facing += Rotation::HalfPi;
                     Rotation::HalfPi
                 }
                 Input::Right => {
-                    self.3 += Rotation::ThreeHalvesPi;
+                    self.facing += Rotation::ThreeHalvesPi;
                     Rotation::ThreeHalvesPi
                 }
                 _ => Rotation::default(),
@@ -76,51 +169,413 @@ impl Entity for Player {
     }
 
     fn sprite(&self) -> &Rc<Sprite> {
-        &self.1
+        &self.sprite
     }
 
-    fn collision(&mut self, other: &mut Box<dyn Entity>) {
-        other.effect(Effect::Damage(10));
+    fn collision(&mut self, other: &mut Box<dyn Entity>, other_id: EntityId) {
+        if other.has_tag(ENEMY_TAG) {
+            self.hits.push(other_id);
+        }
+    }
+
+    fn draw(&self, draw: &mut Draw) {
+        // health bar floating just above the sprite
+        let width = self.sprite.width() as i32 * self.health.get().max(0) / PLAYER_HEALTH;
+        draw.fill_rect(
+            0,
+            self.sprite.height() as i32 + 1,
+            width,
+            1,
+            HEALTH_BAR_COLOR,
+        );
     }
 
     fn effect(&mut self, effect: Effect) {
-        match effect {
-            _ => {}
+        if let Effect::Damage(damage) = effect {
+            self.health.set(self.health.get() - damage);
         }
     }
 }
 
+/// Flies straight ahead, slowing down and bouncing off the edges, until it hits
+/// something or runs out of time. Stuns any enemies close to what it hits.
 #[derive(Debug)]
-struct Enemy((f32, f32), Rc<Sprite>, i32);
+struct Bullet {
+    sprite: Rc<Sprite>,
+    body: Body,
+    ticks_left: u32,
+    hit: Option<EntityId>,
+}
+
+impl Bullet {
+    fn new(velocity: Vector) -> Self {
+        Self {
+            sprite: Rc::new(Sprite::filled(3, 3, BULLET_COLOR)),
+            body: Body::new(velocity)
+                .with_drag(BULLET_DRAG)
+                .with_restitution(1.0),
+            ticks_left: BULLET_TICKS,
+            hit: None,
+        }
+    }
+}
+
+impl Entity for Bullet {
+    fn start_pos(&self) -> (f32, f32) {
+        // always spawned by the player
+        (0.5, 0.5)
+    }
+
+    fn update(&mut self, _input: Input, world: &mut World) -> Update {
+        if let Some(hit) = self.hit {
+            world.send(hit, Effect::Damage(BULLET_DAMAGE));
+            let nearby =
+                world.within_radius(BULLET_BLAST_RADIUS, |entity| entity.has_tag(ENEMY_TAG));
+            world.broadcast(
+                nearby,
+                Effect::Status(Status::new(StatusKind::Stun, BULLET_STUN_TICKS)),
+            );
+
+            return Update::Destroy;
+        }
+
+        if self.ticks_left == 0 {
+            return Update::Destroy;
+        }
+        self.ticks_left -= 1;
+
+        Update::None
+    }
+
+    fn body(&mut self) -> Option<&mut Body> {
+        Some(&mut self.body)
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Bounce
+    }
+
+    fn sprite(&self) -> &Rc<Sprite> {
+        &self.sprite
+    }
+
+    fn collision(&mut self, _other: &mut Box<dyn Entity>, other_id: EntityId) {
+        // only the first hit counts
+        self.hit.get_or_insert(other_id);
+    }
+
+    fn collider(&self) -> Collider {
+        // only enemies get hit, never the player firing
+        Collider::new(BULLET_LAYER, ENEMY_LAYER, ColliderKind::Trigger)
+    }
+
+    fn effect(&mut self, _effect: Effect) {}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    Patrol,
+    Chase,
+}
+
+#[derive(Debug)]
+struct Enemy {
+    start: (f32, f32),
+    sprite: Rc<Sprite>,
+    health: i32,
+    patrol: Action,
+    mode: StateMachine<Mode, Action>,
+}
+
+/// Published whenever an enemy is destroyed.
+#[derive(Debug)]
+struct EnemyDestroyed;
+
+impl Enemy {
+    fn new(start: (f32, f32), sprite: Rc<Sprite>) -> Self {
+        // pace back and forth, and chase the player once it comes close enough
+        let mode = StateMachine::new(Mode::Patrol)
+            .on_enter(Mode::Patrol, |action: &mut Action| *action = patrol())
+            .on_update(Mode::Patrol, |action, world| action.update(world))
+            .on_update(Mode::Chase, |_, world| match player_in_sight(world) {
+                Some(player) => Update::Action {
+                    step: chase_step(world, player),
+                    rotate: Rotation::Zero,
+                },
+                None => Update::None,
+            })
+            .transition(Mode::Patrol, Mode::Chase, |_, world| {
+                player_in_sight(world).is_some()
+            })
+            .transition(Mode::Chase, Mode::Patrol, |_, world| {
+                player_in_sight(world).is_none()
+            });
+
+        Self {
+            start,
+            sprite,
+            health: ENEMY_HEALTH,
+            patrol: patrol(),
+            mode,
+        }
+    }
+}
 
 impl Entity for Enemy {
     fn start_pos(&self) -> (f32, f32) {
-        self.0
+        self.start
     }
 
-    fn update(&mut self, input: Input) -> Update {
-        if self.2 <= 0 {
+    fn update(&mut self, _input: Input, world: &mut World) -> Update {
+        if self.health <= 0 {
+            world.publish(EnemyDestroyed);
             return Update::Destroy;
         }
 
-        match input {
-            _ => Update::None,
+        self.mode.update(&mut self.patrol, world)
+    }
+
+    fn tags(&self) -> &[&'static str] {
+        &[ENEMY_TAG]
+    }
+
+    fn collider(&self) -> Collider {
+        // enemies can't pile on top of each other while chasing
+        Collider::new(ENEMY_LAYER, PLAYER_LAYER | ENEMY_LAYER, ColliderKind::Solid)
+    }
+
+    fn sprite(&self) -> &Rc<Sprite> {
+        &self.sprite
+    }
+
+    fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+    fn effect(&mut self, effect: Effect) {
+        if let Effect::Damage(damage) = effect {
+            self.health -= damage;
         }
     }
 
+    fn label(&self) -> Option<Label> {
+        Some(Label::new(
+            format!("HP {}", self.health.max(0)),
+            LABEL_COLOR,
+        ))
+    }
+
+    fn debug_state(&self) -> Option<String> {
+        Some(format!("{:?}", self.mode.state()))
+    }
+}
+
+fn player_in_sight(world: &World) -> Option<Seen> {
+    world
+        .nearest_tagged(PLAYER_TAG)
+        .filter(|player| player.distance <= ENEMY_SIGHT)
+}
+
+/// Next step along the shortest way to the player around other enemies, or cutting
+/// it off when there's no way through, while keeping some room from other enemies.
+fn chase_step(world: &World, player: Seen) -> Vector {
+    let grid = world.nav_grid(ENEMY_PATH_CELL_SIZE);
+    let chase = grid
+        .find_path(grid.cell_at(world.center()), grid.cell_at(player.center))
+        .and_then(|path| {
+            grid.steps(world.center(), &path, ENEMY_SPEED)
+                .first()
+                .copied()
+        })
+        .unwrap_or_else(|| steering::pursue(world, &player, ENEMY_SPEED));
+
+    chase
+        + steering::separation(world, ENEMY_SPACING, ENEMY_SPEED / 2.0, |entity| {
+            entity.has_tag(ENEMY_TAG)
+        })
+}
+
+/// Enemies pace left and right while the player is out of sight.
+fn patrol() -> Action {
+    let step = Vector::new(ENEMY_PATROL_SPEED, 0.0);
+    Action::forever(Action::sequence([
+        Action::move_by(step, ENEMY_PATROL_TICKS),
+        Action::wait(ENEMY_PATROL_TICKS / 2),
+        Action::move_by(-step, ENEMY_PATROL_TICKS),
+        Action::wait(ENEMY_PATROL_TICKS / 2),
+    ]))
+}
+
+/// Static text, e.g. for titles.
+#[derive(Debug)]
+struct Text((f32, f32), Rc<Sprite>, Option<&'static str>);
+
+impl Entity for Text {
+    fn start_pos(&self) -> (f32, f32) {
+        self.0
+    }
+
+    fn update(&mut self, _input: Input, _world: &mut World) -> Update {
+        Update::None
+    }
+
     fn sprite(&self) -> &Rc<Sprite> {
         &self.1
     }
 
-    fn collision(&mut self, _other: &mut Box<dyn Entity>) {}
+    fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
 
-    fn effect(&mut self, effect: Effect) {
-        match effect {
-            Effect::Damage(damage) => {
-                self.2 -= damage;
+    fn collider(&self) -> Collider {
+        Collider::disabled()
+    }
+
+    fn effect(&mut self, _effect: Effect) {}
+
+    fn label(&self) -> Option<Label> {
+        self.2.map(|text| Label::new(text, LABEL_COLOR))
+    }
+}
+
+fn text(pos: (f32, f32), text: &str, label: Option<&'static str>) -> Box<dyn Entity> {
+    Box::new(Text(
+        pos,
+        Rc::new(Sprite::from_text(text, TEXT_COLOR)),
+        label,
+    ))
+}
+
+/// Sprites and state shared by every round of the game.
+#[derive(Clone, Debug)]
+struct Game {
+    smiley: Rc<Sprite>,
+    meanie: Rc<Sprite>,
+    // shared between the entities and the HUD
+    health: Rc<Cell<i32>>,
+    score: Rc<Cell<u32>>,
+}
+
+#[derive(Debug)]
+struct Title(Game);
+
+impl Scene for Title {
+    fn starting_entities(&mut self) -> Vec<Box<dyn Entity>> {
+        vec![text((0.5, 0.6), TITLE, Some("arrow keys to start"))]
+    }
+
+    fn update(&mut self, input: Input) -> SceneChange {
+        match input {
+            Input::Up | Input::Down | Input::Left | Input::Right => {
+                SceneChange::Replace(Box::new(Gameplay(self.0.clone())))
+            }
+            _ => SceneChange::None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Gameplay(Game);
+
+impl Scene for Gameplay {
+    fn starting_entities(&mut self) -> Vec<Box<dyn Entity>> {
+        let game = &self.0;
+        game.health.set(PLAYER_HEALTH);
+        game.score.set(0);
+
+        let mut entities: Vec<_> = [
+            (0.2, 0.2),
+            (0.5, 0.2),
+            (0.8, 0.2),
+            (0.2, 0.8),
+            (0.5, 0.8),
+            (0.8, 0.8),
+        ]
+        .into_iter()
+        .map(|pos| Box::new(Enemy::new(pos, game.meanie.clone())) as Box<dyn Entity>)
+        .collect();
+
+        entities.push(Box::new(Player {
+            start: (0.5, 0.5),
+            sprite: game.smiley.clone(),
+            health: game.health.clone(),
+            facing: Rotation::Zero,
+            hits: vec![],
+        }));
+
+        entities
+    }
+
+    fn starting_timers(&mut self) -> Vec<Timer> {
+        vec![Timer::every(WAVE_TIMER, Delay::Seconds(WAVE_SECONDS))]
+    }
+
+    fn on_timer(&mut self, name: &'static str, rng: &mut Rng) -> Vec<Box<dyn Entity>> {
+        if name != WAVE_TIMER {
+            return vec![];
+        }
+
+        // another pair of enemies from either side, somewhere up or down it
+        [
+            (0.1, rng.range_f32(WAVE_HEIGHTS)),
+            (0.9, rng.range_f32(WAVE_HEIGHTS)),
+        ]
+        .into_iter()
+        .map(|pos| Box::new(Enemy::new(pos, self.0.meanie.clone())) as Box<dyn Entity>)
+        .collect()
+    }
+
+    fn update(&mut self, input: Input) -> SceneChange {
+        if self.0.health.get() <= 0 {
+            SceneChange::Replace(Box::new(GameOver(self.0.clone())))
+        } else if input == Input::Escape {
+            SceneChange::Push(Box::new(Pause))
+        } else {
+            SceneChange::None
+        }
+    }
+
+    fn transition(&self) -> Transition {
+        Transition::Fade(6)
+    }
+}
+
+#[derive(Debug)]
+struct Pause;
+
+impl Scene for Pause {
+    fn starting_entities(&mut self) -> Vec<Box<dyn Entity>> {
+        vec![text((0.5, 0.5), "Paused", Some("esc to resume"))]
+    }
+
+    fn update(&mut self, input: Input) -> SceneChange {
+        match input {
+            Input::Escape => SceneChange::Pop,
+            _ => SceneChange::None,
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug)]
+struct GameOver(Game);
+
+impl Scene for GameOver {
+    fn starting_entities(&mut self) -> Vec<Box<dyn Entity>> {
+        vec![text((0.5, 0.6), "Game over", Some("arrow keys to restart"))]
+    }
+
+    fn update(&mut self, input: Input) -> SceneChange {
+        match input {
+            Input::Up | Input::Down | Input::Left | Input::Right => {
+                SceneChange::Replace(Box::new(Gameplay(self.0.clone())))
             }
+            _ => SceneChange::None,
         }
     }
+
+    fn transition(&self) -> Transition {
+        Transition::Wipe(8)
+    }
 }
 
 fn main() -> Result<()> {
@@ -143,34 +598,51 @@ fn main() -> Result<()> {
 
     let smiley_path = Path::new(BMPS_DIR).join(SMILEY_BMP);
     let meanie_path = Path::new(BMPS_DIR).join(MEANIE_BMP);
-    let smiley = Rc::new(Sprite::new(&smiley_path)?);
-    let meanie = Rc::new(Sprite::new(&meanie_path)?);
-
-    let mut entities: Vec<_> = [
-        (0.2, 0.2),
-        (0.5, 0.2),
-        (0.8, 0.2),
-        (0.2, 0.8),
-        (0.5, 0.8),
-        (0.8, 0.8),
-    ]
-    .into_iter()
-    .map(|pos| Box::new(Enemy(pos, meanie.clone(), 5)) as Box<dyn Entity>)
-    .collect();
-
-    entities.push(Box::new(Player(
-        (0.5, 0.5),
-        smiley.clone(),
-        10,
-        Rotation::Zero,
-    )));
-
-    if let Err(error) = Engine::default()
+    let game = Game {
+        smiley: Rc::new(Sprite::new(&smiley_path)?),
+        meanie: Rc::new(Sprite::new(&meanie_path)?),
+        health: Rc::new(Cell::new(PLAYER_HEALTH)),
+        score: Rc::new(Cell::new(0)),
+    };
+    let (health, score) = (game.health.clone(), game.score.clone());
+    let scored = game.score.clone();
+
+    let mut engine = match cli.seed {
+        Some(seed) => Engine::default().set_seed(seed),
+        None => Engine::default(),
+    };
+    if let Some(path) = &cli.record {
+        engine = engine.record_to(path).context("while creating recording")?;
+    }
+    if let Some(path) = &cli.replay {
+        engine = engine
+            .replay_from(path)
+            .context("while loading recording")?;
+    }
+
+    if let Err(error) = engine
         .set_title(TITLE)
         .set_ui_color(UI_COLOR)?
         .set_bg_color(BG_COLOR)?
         .set_fps(cli.fps)?
-        .starting_entities(entities)
+        .hud_element(
+            HudElement::new(Region::Top(3), move || {
+                Content::Text(format!("Score: {}", score.get()))
+            })
+            .title("Score"),
+        )
+        .hud_element(
+            HudElement::new(Region::Top(3), move || Content::Gauge {
+                label: format!("{}/{}", health.get().max(0), PLAYER_HEALTH),
+                ratio: health.get() as f64 / PLAYER_HEALTH as f64,
+            })
+            .title("Health"),
+        )
+        .subscribe(move |_: &EnemyDestroyed| {
+            // score a point for each enemy destroyed
+            scored.set(scored.get() + 1);
+        })
+        .starting_scene(Box::new(Title(game)))
         .init()
         .context("while rendering snake game")
     {
@@ -0,0 +1,174 @@
+use ratatui::{
+    prelude::*,
+    widgets::canvas::{Context, Painter},
+};
+use std::fmt::{self, Debug, Formatter};
+
+use crate::{paint_pixel, X_SCALE, Y_SCALE};
+
+/// Immediate-mode drawing, collected fresh every frame.
+///
+/// Coordinates are in pixels, the same units as sprites and `Vector`: a pixel is
+/// `X_SCALE` cells wide, so circles and squares come out the right shape.
+/// `y` grows upwards.
+///
+/// Shapes are rasterized here rather than with ratatui's canvas shapes, which
+/// work in canvas cells and would come out squashed by `X_SCALE`. Each pixel is
+/// then painted `X_SCALE` cells wide, like sprites.
+#[derive(Debug, Default)]
+pub struct Draw {
+    origin: (i32, i32),
+    pixels: Vec<(i32, i32, Color)>,
+    texts: Vec<(i32, i32, String, Color)>,
+}
+
+impl Draw {
+    /// Drawing relative to the given canvas position.
+    pub(crate) fn at(x: i32, y: i32) -> Self {
+        Self {
+            origin: (x, y),
+            ..Self::default()
+        }
+    }
+
+    fn to_canvas(&self, x: i32, y: i32) -> (i32, i32) {
+        (self.origin.0 + x * X_SCALE, self.origin.1 + y * Y_SCALE)
+    }
+
+    pub fn point(&mut self, x: i32, y: i32, color: (u8, u8, u8)) {
+        let (x, y) = self.to_canvas(x, y);
+        self.pixels
+            .push((x, y, Color::Rgb(color.0, color.1, color.2)));
+    }
+
+    pub fn points<T>(&mut self, points: T, color: (u8, u8, u8))
+    where
+        T: IntoIterator<Item = (i32, i32)>,
+    {
+        for (x, y) in points {
+            self.point(x, y, color);
+        }
+    }
+
+    pub fn line(&mut self, from: (i32, i32), to: (i32, i32), color: (u8, u8, u8)) {
+        // Bresenham's line algorithm
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let step_x = if x < to.0 { 1 } else { -1 };
+        let step_y = if y < to.1 { 1 } else { -1 };
+        let mut error = dx + dy;
+
+        loop {
+            self.point(x, y, color);
+            if (x, y) == to {
+                break;
+            }
+
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Outline of a rectangle with its bottom-left corner at `x`/`y`.
+    pub fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: (u8, u8, u8)) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, top) = (x + width - 1, y + height - 1);
+
+        self.line((x, y), (right, y), color);
+        self.line((x, top), (right, top), color);
+        self.line((x, y), (x, top), color);
+        self.line((right, y), (right, top), color);
+    }
+
+    /// Solid rectangle with its bottom-left corner at `x`/`y`.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: (u8, u8, u8)) {
+        for row in y..y + height {
+            for column in x..x + width {
+                self.point(column, row, color);
+            }
+        }
+    }
+
+    pub fn circle(&mut self, x: i32, y: i32, radius: i32, color: (u8, u8, u8)) {
+        // midpoint circle algorithm, mirrored into all eight octants
+        let (mut offset_x, mut offset_y) = (radius, 0);
+        let mut error = 1 - radius;
+
+        while offset_x >= offset_y {
+            for (point_x, point_y) in [
+                (offset_x, offset_y),
+                (offset_y, offset_x),
+                (-offset_y, offset_x),
+                (-offset_x, offset_y),
+                (-offset_x, -offset_y),
+                (-offset_y, -offset_x),
+                (offset_y, -offset_x),
+                (offset_x, -offset_y),
+            ] {
+                self.point(x + point_x, y + point_y, color);
+            }
+
+            offset_y += 1;
+            if error < 0 {
+                error += 2 * offset_y + 1;
+            } else {
+                offset_x -= 1;
+                error += 2 * (offset_y - offset_x) + 1;
+            }
+        }
+    }
+
+    pub fn fill_circle(&mut self, x: i32, y: i32, radius: i32, color: (u8, u8, u8)) {
+        for offset_y in -radius..=radius {
+            for offset_x in -radius..=radius {
+                if offset_x * offset_x + offset_y * offset_y <= radius * radius {
+                    self.point(x + offset_x, y + offset_y, color);
+                }
+            }
+        }
+    }
+
+    /// Print text with its left end at `x`/`y`.
+    pub fn text(&mut self, x: i32, y: i32, text: impl Into<String>, color: (u8, u8, u8)) {
+        let (x, y) = self.to_canvas(x, y);
+        self.texts
+            .push((x, y, text.into(), Color::Rgb(color.0, color.1, color.2)));
+    }
+
+    /// Paint everything drawn so far. Anything outside the canvas is clipped.
+    pub(crate) fn render(self, ctx: &mut Context) {
+        {
+            let mut painter = Painter::from(&mut *ctx);
+            for (x, y, color) in self.pixels {
+                paint_pixel(&mut painter, x, y, color);
+            }
+        }
+
+        for (x, y, text, color) in self.texts {
+            ctx.print(
+                x as f64,
+                y as f64,
+                Line::styled(text, Style::default().fg(color)),
+            );
+        }
+    }
+}
+
+/// Game-wide drawing done every frame on top of the entities, e.g. debug overlays.
+pub(crate) struct DrawHook(pub(crate) Box<dyn Fn(&mut Draw)>);
+
+impl Debug for DrawHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DrawHook").finish_non_exhaustive()
+    }
+}
@@ -1,12 +1,22 @@
-use bmp::Image;
+use bmp::{Image, Pixel};
 use std::{
-    fmt::{self, Debug, Formatter},
-    ops::AddAssign,
+    any::Any,
+    fmt::{self, Debug, Display, Formatter},
+    ops::{Add, AddAssign, Mul, Neg, Sub},
     path::Path,
     rc::Rc,
+    str::FromStr,
 };
 
-use crate::GameError;
+use crate::{
+    draw::Draw,
+    event::Event,
+    font,
+    physics::Body,
+    status::{Status, StatusKind},
+    world::World,
+    GameError,
+};
 
 /// Input received from the player.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -16,20 +26,135 @@ pub enum Input {
     Down,
     Left,
     Right,
+    Action,
+    Escape,
     Quit,
 }
 
-/// Used for entities to specify movements/directions.
+impl Input {
+    pub(crate) const ALL: [Input; 8] = [
+        Input::None,
+        Input::Up,
+        Input::Down,
+        Input::Left,
+        Input::Right,
+        Input::Action,
+        Input::Escape,
+        Input::Quit,
+    ];
+
+    /// Name used in recordings. Changing one breaks recordings already made.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Input::None => "None",
+            Input::Up => "Up",
+            Input::Down => "Down",
+            Input::Left => "Left",
+            Input::Right => "Right",
+            Input::Action => "Action",
+            Input::Escape => "Escape",
+            Input::Quit => "Quit",
+        }
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Input {
+    type Err = GameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Input::ALL
+            .into_iter()
+            .find(|input| input.as_str() == name)
+            .ok_or_else(|| GameError::InvalidArg(format!("unknown input {name:?}")))
+    }
+}
+
+/// Handle to an entity that stays valid across ticks.
+/// IDs of destroyed entities are never handed out again.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+impl EntityId {
+    pub(crate) fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self.index as usize
+    }
+
+    pub(crate) fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+/// Used for entities to specify movements/directions, in pixels.
+/// Steps can be fractional, e.g. to move a pixel every other tick.
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Vector {
-    pub x: i32,
-    pub y: i32,
+    pub x: f32,
+    pub y: f32,
 }
 
 impl Vector {
-    pub fn new(x: i32, y: i32) -> Self {
+    pub fn new(x: f32, y: f32) -> Self {
         Self { x, y }
     }
+
+    pub fn length(self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    /// The vector scaled to a length of 1, or zero if it has no length.
+    pub fn normalized(self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self * (1.0 / length)
+        }
+    }
+}
+
+impl Add for Vector {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<f32> for Vector {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
 }
 
 /// Used for entities to specify rotation.
@@ -65,23 +190,210 @@ pub enum Update {
     Destroy,
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Something done to an entity, either directly or sent through the `World`.
+#[derive(Clone, Debug)]
 pub enum Effect {
     Damage(i32),
+    /// A timed status, tracked by the engine when sent through the `World`.
+    Status(Status),
+    /// Any effect the game defines itself, see `Effect::custom`.
+    Custom(Rc<dyn Any>),
+}
+
+impl Effect {
+    pub fn custom<T: Any>(effect: T) -> Self {
+        Effect::Custom(Rc::new(effect))
+    }
+
+    /// The custom effect as its concrete type, if it's a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        match self {
+            Effect::Custom(effect) => effect.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Text printed next to an entity in the game world.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub text: String,
+    pub color: (u8, u8, u8),
+}
+
+impl Label {
+    pub fn new(text: impl Into<String>, color: (u8, u8, u8)) -> Self {
+        Self {
+            text: text.into(),
+            color,
+        }
+    }
+}
+
+/// How an entity takes part in collisions.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColliderKind {
+    /// Reports collisions, but other entities pass through it.
+    #[default]
+    Trigger,
+    /// Reports collisions, and blocks other solid entities from moving into it.
+    Solid,
+    /// Never collides, e.g. for decorations.
+    Disabled,
+}
+
+/// Which entities an entity collides with.
+///
+/// `layers` are the bits an entity is on, and `mask` the bits it collides with.
+/// An entity's `collision` is only called for others on a layer in its mask,
+/// e.g. player bullets can leave the player's layer out of their mask.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Collider {
+    pub layers: u32,
+    pub mask: u32,
+    pub kind: ColliderKind,
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Self {
+            layers: 1,
+            mask: u32::MAX,
+            kind: ColliderKind::Trigger,
+        }
+    }
+}
+
+impl Collider {
+    pub fn new(layers: u32, mask: u32, kind: ColliderKind) -> Self {
+        Self { layers, mask, kind }
+    }
+
+    /// A collider that never collides with anything.
+    pub fn disabled() -> Self {
+        Self {
+            kind: ColliderKind::Disabled,
+            ..Self::default()
+        }
+    }
+
+    /// Whether this collider reports collisions with `other`.
+    pub fn detects(&self, other: &Self) -> bool {
+        self.kind != ColliderKind::Disabled
+            && other.kind != ColliderKind::Disabled
+            && self.mask & other.layers != 0
+    }
+
+    /// Whether this collider is stopped from moving into `other`.
+    pub fn is_blocked_by(&self, other: &Self) -> bool {
+        self.kind == ColliderKind::Solid
+            && other.kind == ColliderKind::Solid
+            && self.mask & other.layers != 0
+    }
+}
+
+/// What happens when an entity tries to move out of bounds.
+/// `Entity::on_out_of_bounds` is called whichever is used.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Stop at the edge, sliding along it.
+    #[default]
+    Clamp,
+    /// Cancel any move that would cross the edge.
+    Block,
+    /// Reappear at the opposite edge.
+    Wrap,
+    /// Stop at the edge, and send the entity's body back the way it came.
+    /// Entities without a body can turn around in `on_out_of_bounds`.
+    Bounce,
+    /// Destroy the entity.
+    Destroy,
+}
+
+/// Edge of the game's bounds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Right,
+    Bottom,
+    Top,
 }
 
 /// A game entity's sprite used for rendering.
 pub struct Sprite {
     image: Image,
+    transparent: Option<(u8, u8, u8)>,
 }
 
 impl Sprite {
     pub fn new(path: &Path) -> Result<Self, GameError> {
         Ok(Self {
             image: bmp::open(path)?,
+            transparent: None,
         })
     }
 
+    /// An invisible sprite, for entities that only need a size (e.g. text labels).
+    pub fn blank(width: u32, height: u32) -> Self {
+        Self {
+            image: Image::new(width, height),
+            transparent: Some((0, 0, 0)),
+        }
+    }
+
+    /// Render `text` with the built-in bitmap font, for titles and signs.
+    /// The background is transparent.
+    pub fn from_text(text: &str, color: (u8, u8, u8)) -> Self {
+        let glyphs = text.chars().count() as u32;
+        let width = (glyphs * (font::GLYPH_WIDTH + font::GLYPH_SPACING))
+            .saturating_sub(font::GLYPH_SPACING);
+
+        // pick a background color that can't be confused with the text
+        let background = (!color.0, !color.1, !color.2);
+        let mut image = Image::new(width, font::GLYPH_HEIGHT);
+        for (x, y) in image.coordinates() {
+            image.set_pixel(x, y, Pixel::new(background.0, background.1, background.2));
+        }
+
+        for (index, c) in text.chars().enumerate() {
+            let left = index as u32 * (font::GLYPH_WIDTH + font::GLYPH_SPACING);
+
+            for (y, row) in font::glyph(c).into_iter().enumerate() {
+                for x in 0..font::GLYPH_WIDTH {
+                    if row & (1 << (font::GLYPH_WIDTH - x - 1)) != 0 {
+                        image.set_pixel(left + x, y as u32, Pixel::new(color.0, color.1, color.2));
+                    }
+                }
+            }
+        }
+
+        Self {
+            image,
+            transparent: Some(background),
+        }
+    }
+
+    /// A sprite filled with a single color.
+    pub fn filled(width: u32, height: u32, color: (u8, u8, u8)) -> Self {
+        let mut image = Image::new(width, height);
+        for (x, y) in image.coordinates() {
+            image.set_pixel(x, y, Pixel::new(color.0, color.1, color.2));
+        }
+
+        Self {
+            image,
+            transparent: None,
+        }
+    }
+
+    /// Treat every pixel of the given color as see-through.
+    pub fn with_transparency(self, color: (u8, u8, u8)) -> Self {
+        Self {
+            transparent: Some(color),
+            ..self
+        }
+    }
+
     pub fn width(&self) -> u32 {
         self.image.get_width()
     }
@@ -94,6 +406,11 @@ impl Sprite {
         let pixel = self.image.get_pixel(x, self.height() - y - 1);
         (pixel.r, pixel.g, pixel.b)
     }
+
+    /// Whether the pixel at `x`/`y` should be skipped when rendering.
+    pub fn is_transparent(&self, x: u32, y: u32) -> bool {
+        self.transparent == Some(self.get_pixel(x, y))
+    }
 }
 
 impl Debug for Sprite {
@@ -101,12 +418,30 @@ impl Debug for Sprite {
         f.debug_struct("Sprite")
             .field("width", &self.width())
             .field("height", &self.height())
+            .field("transparent", &self.transparent)
             .finish()
     }
 }
 
+/// Lets `dyn Entity` be viewed as `dyn Any`. Implemented for every type.
+#[doc(hidden)]
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 /// A game entity of some kind.
-pub trait Entity: Debug {
+pub trait Entity: AsAny + Debug {
     /// Starting position for the entity, between [0, 1).
     fn start_pos(&self) -> (f32, f32);
 
@@ -114,11 +449,92 @@ pub trait Entity: Debug {
     fn sprite(&self) -> &Rc<Sprite>;
 
     /// Update the entity for this game tick.
-    fn update(&mut self, input: Input) -> Update;
+    fn update(&mut self, input: Input, world: &mut World) -> Update;
 
     /// Respond to a collision with another entity.
-    fn collision(&mut self, other: &mut Box<dyn Entity>);
+    /// Called every tick the two overlap, after every entity has moved.
+    ///
+    /// Effects applied straight to `other` skip its statuses, e.g. `Invulnerable`.
+    /// To have them count, note `other_id` and `World::send` the effect next `update`.
+    fn collision(&mut self, other: &mut Box<dyn Entity>, other_id: EntityId);
+
+    /// Respond to another entity starting to overlap this one.
+    /// Called before `collision` on the first tick of contact.
+    fn on_collision_enter(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+    /// Respond to another entity no longer overlapping this one, e.g. because it moved
+    /// away or was destroyed.
+    fn on_collision_exit(&mut self, _other_id: EntityId) {}
 
     /// Respond to an effect.
     fn effect(&mut self, effect: Effect);
+
+    /// Respond to one of the entity's timers going off, just before its `update`.
+    fn on_timer(&mut self, _name: &'static str, _world: &mut World) {}
+
+    /// Respond to an event published through the `World` this tick.
+    fn on_event(&mut self, _event: &Event) {}
+
+    /// Respond to a status sent through the `World` running out.
+    fn on_status_expired(&mut self, _kind: StatusKind) {}
+
+    /// Which entities this entity collides with. Read once, when the entity is added.
+    fn collider(&self) -> Collider {
+        Collider::default()
+    }
+
+    /// What happens when the entity tries to leave the bounds.
+    /// Read once, when the entity is added.
+    fn boundary(&self) -> Boundary {
+        Boundary::Clamp
+    }
+
+    /// Respond to trying to move past an edge of the bounds.
+    fn on_out_of_bounds(&mut self, _edge: Edge) {}
+
+    /// Motion for the engine to integrate every tick, after `update`, if any.
+    fn body(&mut self) -> Option<&mut Body> {
+        None
+    }
+
+    /// Tags other entities can look for, e.g. in `World::nearest_tagged`.
+    /// Looked up once a tick, before any entity updates.
+    fn tags(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Text to print above the entity's sprite, if any.
+    fn label(&self) -> Option<Label> {
+        None
+    }
+
+    /// Current state to show in the debug overlay, e.g. of an embedded `StateMachine`.
+    fn debug_state(&self) -> Option<String> {
+        None
+    }
+
+    /// Draw shapes every frame, relative to the bottom-left of the entity's sprite.
+    fn draw(&self, _draw: &mut Draw) {}
+}
+
+impl dyn Entity {
+    /// Whether the entity is of type `T`.
+    pub fn is<T: Entity>(&self) -> bool {
+        self.as_any().is::<T>()
+    }
+
+    /// The entity as its concrete type, if it's a `T`.
+    pub fn downcast_ref<T: Entity>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// The entity as its concrete type, if it's a `T`.
+    pub fn downcast_mut<T: Entity>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+
+    /// Whether the entity has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().contains(&tag)
+    }
 }
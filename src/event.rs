@@ -0,0 +1,45 @@
+use std::{
+    any::Any,
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+
+/// Something that happened in the game, e.g. an enemy being destroyed, published
+/// with `World::publish`. Any type can be an event.
+///
+/// Events published during a tick are dispatched once every entity has updated and
+/// effects have been delivered: to every entity, then the scene, then the game's
+/// subscribers, in the order they were published.
+#[derive(Clone)]
+pub struct Event(Rc<dyn Any>);
+
+impl Event {
+    pub fn new<T: Any>(event: T) -> Self {
+        Self(Rc::new(event))
+    }
+
+    /// Whether the event is of type `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.is::<T>()
+    }
+
+    /// The event as its concrete type, if it's a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl Debug for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Event").finish_non_exhaustive()
+    }
+}
+
+/// Game-wide handler for events of one type, added with `Engine::subscribe`.
+pub(crate) struct Subscriber(pub(crate) Box<dyn FnMut(&Event)>);
+
+impl Debug for Subscriber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscriber").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt::{self, Debug, Formatter};
+
+use crate::{entity::Update, world::World};
+
+type Hook<T> = Box<dyn Fn(&mut T)>;
+type UpdateHook<T> = Box<dyn Fn(&mut T, &mut World) -> Update>;
+type Guard<T> = Box<dyn Fn(&T, &World) -> bool>;
+
+struct Hooks<S, T> {
+    state: S,
+    enter: Option<Hook<T>>,
+    exit: Option<Hook<T>>,
+    update: Option<UpdateHook<T>>,
+}
+
+struct Transition<S, T> {
+    from: S,
+    to: S,
+    guard: Guard<T>,
+}
+
+/// Finite state machine an entity can embed, e.g. to patrol until it spots the player.
+///
+/// `S` is the set of states, usually a fieldless enum, and `T` the data the hooks work
+/// on, kept by the entity next to the machine. Return the current state from
+/// `Entity::debug_state` to see it in the engine's debug overlay.
+pub struct StateMachine<S, T> {
+    state: S,
+    started: bool,
+    hooks: Vec<Hooks<S, T>>,
+    transitions: Vec<Transition<S, T>>,
+}
+
+impl<S, T> StateMachine<S, T>
+where
+    S: Copy + PartialEq + Debug,
+{
+    pub fn new(initial: S) -> Self {
+        Self {
+            state: initial,
+            started: false,
+            hooks: vec![],
+            transitions: vec![],
+        }
+    }
+
+    fn hooks_mut(&mut self, state: S) -> &mut Hooks<S, T> {
+        let index = match self.hooks.iter().position(|hooks| hooks.state == state) {
+            Some(index) => index,
+            None => {
+                self.hooks.push(Hooks {
+                    state,
+                    enter: None,
+                    exit: None,
+                    update: None,
+                });
+                self.hooks.len() - 1
+            }
+        };
+
+        &mut self.hooks[index]
+    }
+
+    fn hooks(&self, state: S) -> Option<&Hooks<S, T>> {
+        self.hooks.iter().find(|hooks| hooks.state == state)
+    }
+
+    /// Called when the machine moves into `state`, including into the initial state
+    /// on the first update.
+    pub fn on_enter<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: Fn(&mut T) + 'static,
+    {
+        self.hooks_mut(state).enter = Some(Box::new(hook));
+        self
+    }
+
+    /// Called when the machine moves out of `state`.
+    pub fn on_exit<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: Fn(&mut T) + 'static,
+    {
+        self.hooks_mut(state).exit = Some(Box::new(hook));
+        self
+    }
+
+    /// Called every update while in `state`, to decide the entity's `Update`.
+    pub fn on_update<F>(mut self, state: S, hook: F) -> Self
+    where
+        F: Fn(&mut T, &mut World) -> Update + 'static,
+    {
+        self.hooks_mut(state).update = Some(Box::new(hook));
+        self
+    }
+
+    /// Move from `from` to `to` whenever `guard` allows it.
+    /// Transitions are checked in the order they were added, and the first allowed
+    /// one is taken.
+    pub fn transition<F>(mut self, from: S, to: S, guard: F) -> Self
+    where
+        F: Fn(&T, &World) -> bool + 'static,
+    {
+        self.transitions.push(Transition {
+            from,
+            to,
+            guard: Box::new(guard),
+        });
+        self
+    }
+
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Move straight to `state`, running the exit and enter hooks, whatever the guards say.
+    pub fn set(&mut self, state: S, data: &mut T) {
+        if let Some(exit) = self.hooks(self.state).and_then(|hooks| hooks.exit.as_ref()) {
+            exit(data);
+        }
+        self.state = state;
+        if let Some(enter) = self.hooks(state).and_then(|hooks| hooks.enter.as_ref()) {
+            enter(data);
+        }
+    }
+
+    /// Take the first allowed transition, if any, then update the current state.
+    pub fn update(&mut self, data: &mut T, world: &mut World) -> Update {
+        if !self.started {
+            self.started = true;
+            if let Some(enter) = self
+                .hooks(self.state)
+                .and_then(|hooks| hooks.enter.as_ref())
+            {
+                enter(data);
+            }
+        }
+
+        let next = self
+            .transitions
+            .iter()
+            .find(|transition| transition.from == self.state && (transition.guard)(data, world))
+            .map(|transition| transition.to);
+        if let Some(next) = next {
+            self.set(next, data);
+        }
+
+        match self
+            .hooks(self.state)
+            .and_then(|hooks| hooks.update.as_ref())
+        {
+            Some(update) => update(data, world),
+            None => Update::None,
+        }
+    }
+}
+
+impl<S: Debug, T> Debug for StateMachine<S, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateMachine")
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Light {
+        Red,
+        Green,
+    }
+
+    /// What happened, in order, and how long the light's been waiting.
+    #[derive(Default)]
+    struct Log {
+        events: Vec<&'static str>,
+        waited: u32,
+    }
+
+    fn light() -> StateMachine<Light, Log> {
+        StateMachine::<_, Log>::new(Light::Red)
+            .on_enter(Light::Red, |log| log.events.push("enter red"))
+            .on_exit(Light::Red, |log| log.events.push("exit red"))
+            .on_enter(Light::Green, |log| log.events.push("enter green"))
+            .on_update(Light::Red, |log, _| {
+                log.waited += 1;
+                Update::None
+            })
+            .transition(Light::Red, Light::Green, |log, _| log.waited >= 2)
+    }
+
+    #[test]
+    fn transitions_wait_for_their_guard() {
+        let (mut light, mut log) = (light(), Log::default());
+        World::with_empty(|world| {
+            light.update(&mut log, world);
+            light.update(&mut log, world);
+            assert_eq!(light.state(), Light::Red);
+
+            light.update(&mut log, world);
+            assert_eq!(light.state(), Light::Green);
+        });
+    }
+
+    #[test]
+    fn hooks_run_in_order() {
+        let (mut light, mut log) = (light(), Log::default());
+        World::with_empty(|world| {
+            for _ in 0..3 {
+                light.update(&mut log, world);
+            }
+        });
+
+        assert_eq!(log.events, ["enter red", "exit red", "enter green"]);
+    }
+
+    #[test]
+    fn set_ignores_guards_but_runs_hooks() {
+        let (mut light, mut log) = (light(), Log::default());
+        light.set(Light::Green, &mut log);
+
+        assert_eq!(light.state(), Light::Green);
+        assert_eq!(log.events, ["exit red", "enter green"]);
+    }
+}
@@ -0,0 +1,267 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Gauge, List, ListItem, Paragraph, Wrap},
+};
+use std::fmt::{self, Debug, Formatter};
+
+/// Where a HUD element is placed relative to the game canvas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Region {
+    /// Rows reserved above the canvas.
+    Top(u16),
+    /// Rows reserved below the canvas.
+    Bottom(u16),
+    /// Columns reserved left of the canvas.
+    Left(u16),
+    /// Columns reserved right of the canvas.
+    Right(u16),
+    /// Drawn over the canvas. `x` and `y` are between [0, 1], with (0, 0) at the top left.
+    Overlay {
+        x: f32,
+        y: f32,
+        width: u16,
+        height: u16,
+    },
+}
+
+/// Widget content produced by a HUD element each frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Content {
+    Text(String),
+    Gauge { label: String, ratio: f64 },
+    List(Vec<String>),
+}
+
+/// A widget bound to game state, rebuilt every frame.
+pub struct HudElement {
+    region: Region,
+    title: Option<&'static str>,
+    bind: Box<dyn Fn() -> Content>,
+}
+
+impl HudElement {
+    pub fn new<B>(region: Region, bind: B) -> Self
+    where
+        B: Fn() -> Content + 'static,
+    {
+        Self {
+            region,
+            title: None,
+            bind: Box::new(bind),
+        }
+    }
+
+    pub fn title(self, title: &'static str) -> Self {
+        Self {
+            title: Some(title),
+            ..self
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(
+        &self,
+        frame: &mut Frame<'_, B>,
+        area: Rect,
+        ui_color: Color,
+        bg_color: Color,
+    ) {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(ui_color))
+            .style(Style::default().fg(ui_color).bg(bg_color));
+        if let Some(title) = self.title {
+            block = block
+                .title(format!(" {title} "))
+                .title_style(Style::default().add_modifier(Modifier::BOLD));
+        }
+
+        match (self.bind)() {
+            Content::Text(text) => {
+                frame.render_widget(
+                    Paragraph::new(text).wrap(Wrap { trim: true }).block(block),
+                    area,
+                );
+            }
+
+            Content::Gauge { label, ratio } => {
+                // e.g. 0/0 from an empty bar, which the gauge would panic on
+                let ratio = if ratio.is_finite() { ratio } else { 0.0 };
+                frame.render_widget(
+                    Gauge::default()
+                        .block(block)
+                        .gauge_style(Style::default().fg(ui_color).bg(bg_color))
+                        .label(label)
+                        .ratio(ratio.clamp(0.0, 1.0)),
+                    area,
+                );
+            }
+
+            Content::List(items) => {
+                let items: Vec<_> = items.into_iter().map(ListItem::new).collect();
+                frame.render_widget(List::new(items).block(block), area);
+            }
+        }
+    }
+}
+
+impl Debug for HudElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HudElement")
+            .field("region", &self.region)
+            .field("title", &self.title)
+            .finish()
+    }
+}
+
+/// Split `area` between the canvas and the HUD elements.
+/// Returns the canvas area and one area per element, in the same order.
+pub(crate) fn layout(elements: &[HudElement], area: Rect) -> (Rect, Vec<Rect>) {
+    let mut canvas = area;
+    let mut areas = vec![Rect::default(); elements.len()];
+
+    // edges are carved off the canvas in order: top and bottom rows span the
+    // full width, then left and right columns take from what remains
+    for (element, element_area) in elements.iter().zip(areas.iter_mut()) {
+        match element.region {
+            Region::Top(rows) => {
+                let rows = rows.min(canvas.height);
+                *element_area = Rect::new(canvas.x, canvas.y, canvas.width, rows);
+                canvas.y += rows;
+                canvas.height -= rows;
+            }
+            Region::Bottom(rows) => {
+                let rows = rows.min(canvas.height);
+                canvas.height -= rows;
+                *element_area = Rect::new(canvas.x, canvas.bottom(), canvas.width, rows);
+            }
+            _ => {}
+        }
+    }
+    for (element, element_area) in elements.iter().zip(areas.iter_mut()) {
+        match element.region {
+            Region::Left(columns) => {
+                let columns = columns.min(canvas.width);
+                *element_area = Rect::new(canvas.x, canvas.y, columns, canvas.height);
+                canvas.x += columns;
+                canvas.width -= columns;
+            }
+            Region::Right(columns) => {
+                let columns = columns.min(canvas.width);
+                canvas.width -= columns;
+                *element_area = Rect::new(canvas.right(), canvas.y, columns, canvas.height);
+            }
+            _ => {}
+        }
+    }
+    for (element, element_area) in elements.iter().zip(areas.iter_mut()) {
+        if let Region::Overlay {
+            x,
+            y,
+            width,
+            height,
+        } = element.region
+        {
+            let width = width.min(canvas.width);
+            let height = height.min(canvas.height);
+            let x = canvas.x + ((canvas.width - width) as f32 * x.clamp(0.0, 1.0)) as u16;
+            let y = canvas.y + ((canvas.height - height) as f32 * y.clamp(0.0, 1.0)) as u16;
+            *element_area = Rect::new(x, y, width, height);
+        }
+    }
+
+    (canvas, areas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    const AREA: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 40,
+        height: 20,
+    };
+
+    fn element(region: Region) -> HudElement {
+        HudElement::new(region, || Content::Text(String::new()))
+    }
+
+    #[test]
+    fn top_and_bottom_span_the_width_before_the_sides_are_carved() {
+        let elements = [
+            element(Region::Left(5)),
+            element(Region::Top(3)),
+            element(Region::Right(4)),
+            element(Region::Bottom(2)),
+        ];
+        let (canvas, areas) = layout(&elements, AREA);
+
+        assert_eq!(areas[1], Rect::new(0, 0, 40, 3));
+        assert_eq!(areas[3], Rect::new(0, 18, 40, 2));
+        assert_eq!(areas[0], Rect::new(0, 3, 5, 15));
+        assert_eq!(areas[2], Rect::new(36, 3, 4, 15));
+        assert_eq!(canvas, Rect::new(5, 3, 31, 15));
+    }
+
+    #[test]
+    fn overlays_are_placed_within_the_canvas() {
+        let overlay = |x, y| {
+            element(Region::Overlay {
+                x,
+                y,
+                width: 10,
+                height: 4,
+            })
+        };
+        let elements = [
+            element(Region::Top(4)),
+            overlay(0.0, 0.0),
+            overlay(1.0, 1.0),
+            overlay(0.5, 0.5),
+        ];
+        let (canvas, areas) = layout(&elements, AREA);
+
+        assert_eq!(canvas, Rect::new(0, 4, 40, 16));
+        assert_eq!(areas[1], Rect::new(0, 4, 10, 4));
+        assert_eq!(areas[2], Rect::new(30, 16, 10, 4));
+        assert_eq!(areas[3], Rect::new(15, 10, 10, 4));
+    }
+
+    #[test]
+    fn regions_bigger_than_the_area_take_what_is_left() {
+        let elements = [
+            element(Region::Top(15)),
+            element(Region::Bottom(15)),
+            element(Region::Left(50)),
+            element(Region::Overlay {
+                x: 0.5,
+                y: 0.5,
+                width: 100,
+                height: 100,
+            }),
+        ];
+        let (canvas, areas) = layout(&elements, AREA);
+
+        assert_eq!(areas[0], Rect::new(0, 0, 40, 15));
+        assert_eq!(areas[1], Rect::new(0, 15, 40, 5));
+        assert_eq!(areas[2], Rect::new(0, 15, 40, 0));
+        assert_eq!(canvas, Rect::new(40, 15, 0, 0));
+        assert_eq!(areas[3].area(), 0);
+    }
+
+    #[test]
+    fn gauges_with_no_ratio_render_empty() {
+        let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+        let gauge = HudElement::new(Region::Top(3), || Content::Gauge {
+            label: String::new(),
+            ratio: f64::NAN,
+        });
+
+        terminal
+            .draw(|frame| gauge.render(frame, frame.size(), Color::White, Color::Black))
+            .unwrap();
+    }
+}
@@ -14,10 +14,13 @@ use ratatui::{
     Terminal,
 };
 use std::{
+    any::Any,
     cell::{Cell, RefCell},
+    collections::BTreeSet,
     fmt::{self, Debug, Formatter},
     io,
     ops::{AddAssign, RangeInclusive},
+    path::Path,
     rc::Rc,
     sync::{Arc, Mutex},
     thread,
@@ -26,18 +29,56 @@ use std::{
 use thiserror::Error;
 use tracing::{debug, instrument};
 
+pub mod behavior;
+pub mod draw;
 pub mod entity;
-use entity::{Entity, Input, Rotation, Sprite, Update, Vector};
+pub mod event;
+mod font;
+pub mod fsm;
+pub mod hud;
+pub mod pathfinding;
+pub mod physics;
+pub mod random;
+mod replay;
+pub mod scene;
+// only public for the benchmarks
+#[cfg(feature = "bench")]
+pub mod spatial;
+#[cfg(not(feature = "bench"))]
+mod spatial;
+pub mod status;
+pub mod steering;
+pub mod timer;
+pub mod world;
+use draw::{Draw, DrawHook};
+use entity::{
+    Boundary, Collider, Edge, Effect, Entity, EntityId, Input, Rotation, Sprite, Update, Vector,
+};
+use event::Subscriber;
+use hud::HudElement;
+use random::Rng;
+use replay::{Recorder, Replay};
+use scene::{Playing, Root, Scene, SceneChange, SceneState};
+use spatial::{Bounds, SpatialGrid};
+use status::Statuses;
+use timer::Timers;
+use world::{QueryIndex, Queued, Spawn, World};
 
 const FPS_BOUNDS: RangeInclusive<u32> = 1..=30;
 const DEFAULT_FPS: u32 = 15;
 const DEFAULT_TITLE: &str = "Game";
 const DEFAULT_UI_COLOR: &str = "#000000";
 const DEFAULT_BG_COLOR: &str = "#666666";
+const SLOW_MOTION_BOUNDS: RangeInclusive<u32> = 1..=8;
+// cycled through by the slow motion key
+const SLOW_MOTION_SPEEDS: [u32; 4] = [1, 2, 4, 8];
 
 const X_SCALE: i32 = 2; // compensate for squished sprites
 const Y_SCALE: i32 = 1;
 
+/// Furthest, in pixels, an entity placed inside a solid is moved to get it out.
+const MAX_PUSH_OUT: i32 = 16;
+
 /// Error returned from the game.
 /// Use UpdateError when `update` is called on an `Entity`.
 #[derive(Error, Debug)]
@@ -60,64 +101,207 @@ pub enum GameError {
     #[error("invalid argument: {}", .0)]
     InvalidArg(String),
 
+    #[error("invalid replay: {}", .0)]
+    InvalidReplay(String),
+
     #[error("unknown error")]
     Unknown,
 }
 
+/// Where an entity's sprite starts, in canvas coordinates.
+/// Kept fractional so slow movement builds up, and only rounded to draw and collide.
 #[derive(Copy, Clone, Debug)]
 struct Position {
-    x: i32,
-    y: i32,
+    x: f32,
+    y: f32,
+}
+
+impl Position {
+    fn round(self) -> (i32, i32) {
+        (self.x.round() as i32, self.y.round() as i32)
+    }
 }
 
 impl AddAssign<Vector> for Position {
     fn add_assign(&mut self, rhs: Vector) {
-        self.x += rhs.x * X_SCALE;
-        self.y += rhs.y * Y_SCALE;
+        self.x += rhs.x * X_SCALE as f32;
+        self.y += rhs.y * Y_SCALE as f32;
     }
 }
 
 #[derive(Debug)]
 struct EntityState {
+    id: EntityId,
     pos: Option<Position>,
     rot: Rotation,
     sprite: Rc<Sprite>,
+    collider: Collider,
+    boundary: Boundary,
+    statuses: Statuses,
+    /// How far the entity moved last tick, in pixels.
+    velocity: Vector,
     entity: Option<Box<dyn Entity>>,
 }
 
 impl EntityState {
+    /// Box used for collisions, in canvas coordinates.
+    fn bounding_box(&self) -> Bounds {
+        let (x, y) = self.pos.expect("self has a position").round();
+
+        Bounds::new(
+            x,
+            y,
+            x + ((self.sprite.width() as i32 - 2) * X_SCALE),
+            y + ((self.sprite.height() as i32 - 2) * Y_SCALE),
+        )
+    }
+
     fn overlaps(&self, other: &Self) -> bool {
-        let Position {
-            x: self_x,
-            y: self_y,
-        } = self.pos.expect("self has a position");
-        let Position {
-            x: other_x,
-            y: other_y,
-        } = other.pos.expect("other has a position");
+        self.bounding_box().overlaps(&other.bounding_box())
+    }
+
+    /// Middle of the entity's sprite.
+    fn center(&self) -> (f32, f32) {
+        let Position { x, y } = self.pos.expect("self has a position");
+
+        (
+            x + (self.sprite.width() * X_SCALE as u32) as f32 / 2.0,
+            y + (self.sprite.height() * Y_SCALE as u32) as f32 / 2.0,
+        )
+    }
 
-        self_x <= other_x + ((other.sprite.width() as i32 - 2) * X_SCALE)
-            && self_x + ((self.sprite.width() as i32 - 2) * X_SCALE) >= other_x
-            && self_y <= other_y + ((other.sprite.height() as i32 - 2) * Y_SCALE)
-            && self_y + ((self.sprite.height() as i32 - 2) * Y_SCALE) >= other_y
+    /// Position the entity so its middle is at `x`/`y`.
+    fn center_at(&mut self, x: f32, y: f32) {
+        // subtract half the entity's width/height so position is middle of entity
+        self.pos = Some(Position {
+            x: x - (self.sprite.width() * X_SCALE as u32) as f32 / 2.0,
+            y: y - (self.sprite.height() * Y_SCALE as u32) as f32 / 2.0,
+        });
     }
 
     fn within_bounds(&self, bounds: Rect) -> bool {
-        let Position {
-            x: self_x,
-            y: self_y,
-        } = self.pos.expect("self has a position");
+        self.edges_outside(bounds).is_empty()
+    }
+
+    /// Lowest and highest positions that keep the sprite within `bounds`.
+    fn position_range(&self, bounds: Rect) -> (Position, Position) {
+        let width = (self.sprite.width() as i32 - 2) * X_SCALE;
+        let height = (self.sprite.height() as i32 - 2) * Y_SCALE;
+
+        (
+            Position {
+                x: (bounds.left() as i32 + 2) as f32,
+                y: bounds.top() as f32,
+            },
+            Position {
+                x: (bounds.right() as i32 - 2 - width) as f32,
+                y: (bounds.bottom() as i32 - 1 - height) as f32,
+            },
+        )
+    }
+
+    /// Edges of `bounds` the entity sticks out past.
+    fn edges_outside(&self, bounds: Rect) -> Vec<Edge> {
+        let (x, y) = self.pos.expect("self has a position").round();
+        let (min, max) = self.position_range(bounds);
+
+        [
+            ((x as f32) < min.x, Edge::Left),
+            (x as f32 > max.x, Edge::Right),
+            ((y as f32) < min.y, Edge::Bottom),
+            (y as f32 > max.y, Edge::Top),
+        ]
+        .into_iter()
+        .filter_map(|(outside, edge)| outside.then_some(edge))
+        .collect()
+    }
+
+    /// Tell the entity it tried to cross `edges`, destroying it if that's its boundary.
+    fn hit_edges(&mut self, edges: &[Edge]) {
+        let Some(entity) = self.entity.as_mut() else {
+            return;
+        };
+        for edge in edges {
+            entity.on_out_of_bounds(*edge);
+        }
+
+        if self.boundary == Boundary::Destroy && !edges.is_empty() {
+            self.entity = None;
+        }
+    }
+
+    /// Bring the entity back within `bounds` after moving, according to its boundary.
+    fn return_within(&mut self, bounds: Rect) {
+        if self.boundary != Boundary::Wrap {
+            self.clamp_within(bounds);
+            return;
+        }
+
+        let (min, max) = self.position_range(bounds);
+        let edges = self.edges_outside(bounds);
+        let pos = self.pos.as_mut().expect("self has a position");
+        for edge in edges {
+            match edge {
+                Edge::Left => pos.x = max.x,
+                Edge::Right => pos.x = min.x,
+                Edge::Bottom => pos.y = max.y,
+                Edge::Top => pos.y = min.y,
+            }
+        }
+    }
 
-        self_x > bounds.left() as i32 + 1
-            && self_x + ((self.sprite.width() as i32 - 2) * X_SCALE) < bounds.right() as i32 - 1
-            && self_y >= bounds.top() as i32
-            && self_y + ((self.sprite.height() as i32 - 2) * Y_SCALE) < bounds.bottom() as i32
+    /// Move the entity to the nearest position within `bounds`, whatever its boundary.
+    /// Used when placing entities, since only movement wraps around.
+    fn clamp_within(&mut self, bounds: Rect) {
+        let (min, max) = self.position_range(bounds);
+        let pos = self.pos.as_mut().expect("self has a position");
+
+        pos.x = pos.x.max(min.x).min(max.x);
+        pos.y = pos.y.max(min.y).min(max.y);
+    }
+}
+
+/// Hands out generational entity IDs, reusing the slots of destroyed entities.
+#[derive(Debug, Default)]
+struct EntityIds {
+    generations: Vec<u32>,
+    free: Vec<u32>,
+}
+
+impl EntityIds {
+    fn allocate(&mut self) -> EntityId {
+        if let Some(index) = self.free.pop() {
+            EntityId::new(index, self.generations[index as usize])
+        } else {
+            self.generations.push(0);
+            EntityId::new(self.generations.len() as u32 - 1, 0)
+        }
+    }
+
+    fn free(&mut self, id: EntityId) {
+        // bumping the generation invalidates any copies of the old ID
+        self.generations[id.index()] += 1;
+        self.free.push(id.index() as u32);
+    }
+
+    fn is_alive(&self, id: EntityId) -> bool {
+        self.generations.get(id.index()) == Some(&id.generation())
     }
 }
 
 struct State {
     bounds: Option<Rect>,
     entity_states: Vec<RefCell<EntityState>>,
+    ids: EntityIds,
+    // indexed by EntityId::index, giving the entity's index in entity_states
+    lookup: Vec<usize>,
+    // holds indices into entity_states, rebuilt every tick
+    grid: SpatialGrid,
+    // likewise, by tag and for solids
+    index: QueryIndex,
+    // (detector, other) pairs that were touching last tick
+    contacts: BTreeSet<(EntityId, EntityId)>,
+    timers: Timers,
 }
 
 impl State {
@@ -125,6 +309,12 @@ impl State {
         Self {
             bounds: None,
             entity_states: vec![],
+            ids: EntityIds::default(),
+            lookup: vec![],
+            grid: SpatialGrid::default(),
+            index: QueryIndex::default(),
+            contacts: BTreeSet::new(),
+            timers: Timers::default(),
         }
     }
 
@@ -132,21 +322,43 @@ impl State {
         self.bounds = Some(bounds);
     }
 
-    fn add_entity(&mut self, entity: Box<dyn Entity>) {
+    fn add_entity(&mut self, entity: Box<dyn Entity>) -> EntityId {
+        let id = self.ids.allocate();
+        self.insert_entity(id, entity);
+        id
+    }
+
+    /// Add an entity under an ID that's already been allocated.
+    fn insert_entity(&mut self, id: EntityId, entity: Box<dyn Entity>) {
         let sprite = entity.sprite().clone();
+        let collider = entity.collider();
+        let boundary = entity.boundary();
+
+        if self.lookup.len() <= id.index() {
+            self.lookup.resize(id.index() + 1, 0);
+        }
+        self.lookup[id.index()] = self.entity_states.len();
 
         let entity_state = EntityState {
+            id,
             pos: None,
             rot: Rotation::Zero,
             sprite,
+            collider,
+            boundary,
+            statuses: Statuses::default(),
+            velocity: Vector::default(),
             entity: Some(entity),
         };
 
         self.entity_states.push(RefCell::new(entity_state));
     }
 
+    /// Place entities that don't have a position yet, bringing any that start out of
+    /// bounds back within them according to their boundary.
     fn set_starting_positions(&mut self) -> Result<(), GameError> {
-        let bounds = &self.bounds.expect("bounds should exist");
+        let bounds = self.bounds.expect("bounds should exist");
+        let mut placed = vec![];
 
         // for all entity states with no position set, call Entity::start_pos to assign a position
         for entity_state in self
@@ -160,33 +372,59 @@ impl State {
                 .as_ref()
                 .expect("all entities should be Some")
                 .start_pos();
-            let sprite = &entity_state.sprite;
 
-            // Position is the provided x/y positions times the screen width/height.
-            // Subtract half the entity's width/height so position is middle of entity.
-            let pos = Position {
-                x: (((bounds.right() - bounds.left()) as f32 * x)
-                    - ((sprite.width() * X_SCALE as u32) as f32 / 2.0)) as i32,
-                y: (((bounds.bottom() - bounds.top()) as f32 * y)
-                    - ((sprite.height() * Y_SCALE as u32) as f32 / 2.0)) as i32,
-            };
+            // position is the provided x/y positions times the screen width/height
+            entity_state.center_at(
+                (bounds.right() - bounds.left()) as f32 * x,
+                (bounds.bottom() - bounds.top()) as f32 * y,
+            );
+            placed.push(entity_state.id);
+            if entity_state.within_bounds(bounds) {
+                continue;
+            }
+
+            debug!(?entity_state, "starting position out of bounds");
+            if entity_state.boundary == Boundary::Destroy {
+                entity_state.entity = None;
+                continue;
+            }
+            entity_state.clamp_within(bounds);
 
-            entity_state.pos = Some(pos);
-            if !entity_state.within_bounds(self.bounds.unwrap()) {
+            // the sprite is too big to ever fit
+            if !entity_state.within_bounds(bounds) {
                 return Err(GameError::OutOfBounds);
             }
         }
+
+        let ids = &mut self.ids;
+        self.entity_states.retain(|entity_state| {
+            let entity_state = entity_state.borrow();
+            if entity_state.entity.is_none() {
+                ids.free(entity_state.id);
+            }
+            entity_state.entity.is_some()
+        });
+        self.reindex();
+
+        let placed: Vec<_> = placed.into_iter().filter_map(|id| self.find(id)).collect();
+        self.push_out_of_solids(&placed);
         debug!("starting positions set");
 
         Ok(())
     }
 
-    fn render_entities(&self, ctx: &mut Context) -> Result<(), GameError> {
-        let mut painter = Painter::from(ctx);
+    /// Paint every entity's sprite, drawings and label.
+    /// With an `inspect` color, each entity's `debug_state` and statuses are printed
+    /// below it too.
+    fn render_entities(&self, ctx: &mut Context, inspect: Option<Color>) -> Result<(), GameError> {
+        let mut painter = Painter::from(&mut *ctx);
+        let mut labels = vec![];
+        let mut debug_states = vec![];
+        let mut draws = vec![];
 
         for entity_state in &self.entity_states {
             let entity_state = entity_state.borrow();
-            let pos = entity_state.pos.expect("entity has a position");
+            let (pos_x, pos_y) = entity_state.pos.expect("entity has a position").round();
             let sprite = &entity_state.sprite;
 
             let (x_range, y_range) = if let Rotation::Zero | Rotation::Pi = entity_state.rot {
@@ -195,79 +433,521 @@ impl State {
                 (0..sprite.height(), 0..sprite.width())
             };
 
+            if let Some(label) = entity_state
+                .entity
+                .as_ref()
+                .and_then(|entity| entity.label())
+            {
+                // print just above the sprite
+                let height = y_range.end as i32 * Y_SCALE;
+                labels.push((pos_x as f64, (pos_y + height) as f64, label));
+            }
+
+            if let Some(debug_state) = inspect.and_then(|_| {
+                let state = entity_state
+                    .entity
+                    .as_ref()
+                    .and_then(|entity| entity.debug_state());
+                match (state, entity_state.statuses.describe()) {
+                    (Some(state), Some(statuses)) => Some(format!("{state} [{statuses}]")),
+                    (state, statuses) => state.or(statuses),
+                }
+            }) {
+                // print just below the sprite
+                debug_states.push((pos_x as f64, (pos_y - 1) as f64, debug_state));
+            }
+
             for x in x_range {
                 for y in y_range.clone() {
-                    let rgb = match entity_state.rot {
-                        Rotation::Zero => sprite.get_pixel(x, y),
-                        Rotation::HalfPi => {
-                            sprite.get_pixel(sprite.width() - y - 1, sprite.height() - x - 1)
-                        }
-                        Rotation::Pi => {
-                            sprite.get_pixel(sprite.width() - x - 1, sprite.height() - y - 1)
-                        }
-                        Rotation::ThreeHalvesPi => sprite.get_pixel(y, x),
+                    let (sprite_x, sprite_y) = match entity_state.rot {
+                        Rotation::Zero => (x, y),
+                        Rotation::HalfPi => (sprite.width() - y - 1, sprite.height() - x - 1),
+                        Rotation::Pi => (sprite.width() - x - 1, sprite.height() - y - 1),
+                        Rotation::ThreeHalvesPi => (y, x),
                     };
+                    if sprite.is_transparent(sprite_x, sprite_y) {
+                        continue;
+                    }
 
+                    let rgb = sprite.get_pixel(sprite_x, sprite_y);
                     let color = Color::Rgb(rgb.0, rgb.1, rgb.2);
 
-                    let (x_offset, y_offset) = painter
-                        .get_point(
-                            (pos.x + (x as i32 * X_SCALE)) as f64,
-                            (pos.y + (y as i32 * Y_SCALE)) as f64,
-                        )
-                        .ok_or(GameError::OutOfBounds)?;
-
-                    // sprites will look squished unless scaling factor is accounted for
-                    for x in 0..X_SCALE {
-                        for y in 0..Y_SCALE {
-                            painter.paint(x_offset - x as usize, y_offset - y as usize, color);
-                        }
-                    }
+                    paint_pixel(
+                        &mut painter,
+                        pos_x + (x as i32 * X_SCALE),
+                        pos_y + (y as i32 * Y_SCALE),
+                        color,
+                    )
+                    .ok_or(GameError::OutOfBounds)?;
                 }
             }
+
+            if let Some(entity) = entity_state.entity.as_ref() {
+                let mut draw = Draw::at(pos_x, pos_y);
+                entity.draw(&mut draw);
+                draws.push(draw);
+            }
+        }
+
+        // drawings go on a layer above all the sprites
+        ctx.layer();
+        for draw in draws {
+            draw.render(ctx);
+        }
+
+        // labels are printed over every layer, so they're never hidden by sprites
+        for (x, y, label) in labels {
+            let (r, g, b) = label.color;
+            ctx.print(
+                x,
+                y,
+                Line::styled(label.text, Style::default().fg(Color::Rgb(r, g, b))),
+            );
+        }
+
+        if let Some(color) = inspect {
+            for (x, y, debug_state) in debug_states {
+                ctx.print(x, y, Line::styled(debug_state, Style::default().fg(color)));
+            }
         }
 
         Ok(())
     }
 
-    fn update_entities(&mut self, input: Input) -> Result<(), GameError> {
-        for (index, entity_state) in self.entity_states.iter().enumerate() {
-            let mut entity_state = entity_state.borrow_mut();
+    /// Rebuild the ID lookup after entities have been removed.
+    fn reindex(&mut self) {
+        for (index, entity_state) in self.entity_states.iter_mut().enumerate() {
+            self.lookup[entity_state.get_mut().id.index()] = index;
+        }
+    }
 
-            // Get mut borrows for all other entity states. The run-time borrow checking
-            // will pass because even though entity_state has been mut borrowed already,
-            // its index is used to filter it out from the iter.
-            for mut other_entity_state in self
+    /// Add entities spawned during the tick. Any that land out of bounds are moved
+    /// back within them, or dropped if their boundary is `Destroy` or they can't fit.
+    fn add_spawns(&mut self, spawns: Vec<Spawn>) {
+        let bounds = self.bounds.expect("bounds should exist");
+        let mut added = vec![];
+
+        for Spawn {
+            id,
+            entity,
+            center: (x, y),
+        } in spawns
+        {
+            self.insert_entity(id, entity);
+
+            let entity_state = self
                 .entity_states
+                .last_mut()
+                .expect("entity was just added")
+                .get_mut();
+            entity_state.center_at(x, y);
+            if entity_state.boundary != Boundary::Destroy {
+                entity_state.clamp_within(bounds);
+            }
+
+            if !entity_state.within_bounds(bounds) {
+                debug!(?entity_state, "spawned entity out of bounds");
+                self.entity_states.pop();
+                self.ids.free(id);
+            } else {
+                added.push(self.entity_states.len() - 1);
+            }
+        }
+
+        self.push_out_of_solids(&added);
+    }
+
+    /// Indices of the solid entities the entity overlaps and can't move into.
+    /// The entity itself must already be borrowed, so it's skipped.
+    fn blockers(&self, entity_state: &EntityState) -> Vec<usize> {
+        self.grid
+            .query(entity_state.bounding_box())
+            .into_iter()
+            .filter(|&index| {
+                self.entity_states[index]
+                    .try_borrow()
+                    .is_ok_and(|other_entity_state| {
+                        other_entity_state.entity.is_some()
+                            && entity_state
+                                .collider
+                                .is_blocked_by(&other_entity_state.collider)
+                            && entity_state.overlaps(&other_entity_state)
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether the entity can be where it is: in bounds and not inside a solid, other
+    /// than those at `ignored` indices. Entities that wrap around are free to leave
+    /// the bounds.
+    fn is_free(&self, entity_state: &EntityState, ignored: &[usize]) -> bool {
+        (entity_state.boundary == Boundary::Wrap
+            || entity_state.within_bounds(self.bounds.expect("bounds should exist")))
+            && self
+                .blockers(entity_state)
                 .iter()
-                .enumerate()
-                .filter(|(other_index, _)| *other_index != index)
-                .map(|(_, entity_state)| entity_state.borrow_mut())
+                .all(|index| ignored.contains(index))
+    }
+
+    /// Whether the entity would be free if it were moved by `offset`.
+    fn is_free_at(
+        &self,
+        entity_state: &mut EntityState,
+        offset: Vector,
+        ignored: &[usize],
+    ) -> bool {
+        let start = entity_state.pos;
+        *entity_state.pos.as_mut().expect("entity has a position") += offset;
+        let is_free = self.is_free(entity_state, ignored);
+        entity_state.pos = start;
+
+        is_free
+    }
+
+    /// Nearest offset, up to `MAX_PUSH_OUT` pixels away, that gets the entity out of
+    /// any solids it's in.
+    fn push_out(&self, entity_state: &mut EntityState) -> Option<Vector> {
+        if self.is_free(entity_state, &[]) {
+            return Some(Vector::default());
+        }
+
+        (1..=MAX_PUSH_OUT).find_map(|ring| {
+            // every offset on the edge of a square `ring` pixels out, nearest first
+            let mut offsets: Vec<_> = (-ring..=ring)
+                .flat_map(|x| (-ring..=ring).map(move |y| (x, y)))
+                .filter(|(x, y)| x.abs() == ring || y.abs() == ring)
+                .map(|(x, y)| Vector::new(x as f32, y as f32))
+                .collect();
+            offsets.sort_by(|a, b| a.length().total_cmp(&b.length()));
+
+            offsets
+                .into_iter()
+                .find(|offset| self.is_free_at(entity_state, *offset, &[]))
+        })
+    }
+
+    /// Move newly placed entities at `indices` out of any solids they landed in.
+    /// Ones with nowhere to go stay put, and can still move out on their own.
+    fn push_out_of_solids(&mut self, indices: &[usize]) {
+        if indices.is_empty() {
+            return;
+        }
+        self.rebuild_broad_phase();
+
+        for &index in indices {
+            let (old_box, new_box) = {
+                let mut entity_state = self.entity_states[index].borrow_mut();
+                let old_box = entity_state.bounding_box();
+                if let Some(offset) = self.push_out(&mut entity_state) {
+                    *entity_state.pos.as_mut().expect("entity has a position") += offset;
+                }
+                (old_box, entity_state.bounding_box())
+            };
+
+            self.grid.update(index, old_box, new_box);
+        }
+    }
+
+    /// Rebuild the spatial grid and query index from scratch.
+    fn rebuild_broad_phase(&mut self) {
+        self.grid.clear();
+        self.index.clear();
+        for (index, entity_state) in self.entity_states.iter_mut().enumerate() {
+            let entity_state = entity_state.get_mut();
+            self.grid.insert(index, entity_state.bounding_box());
+            self.index.insert(index, entity_state);
+        }
+    }
+
+    /// Move the entity by `step` a pixel at a time, so it can't tunnel through thin
+    /// solids. When blocked, it keeps whichever axis of the movement is still free,
+    /// sliding along walls, and stops once neither is. Solids it's already inside
+    /// don't block it, so it can always get back out. Returns how far it moved.
+    fn sweep(&self, entity_state: &mut EntityState, step: Vector) -> Vector {
+        let pixels = step.x.abs().max(step.y.abs()).ceil() as i32;
+        let mut moved = Vector::default();
+        let stuck_in = self.blockers(entity_state);
+
+        let along = |pixel: i32| step * (pixel as f32 / pixels as f32);
+
+        for pixel in 1..=pixels {
+            // advance a pixel from wherever each axis got to, so one held back by a
+            // wall can't catch up by jumping past it
+            let (last, target) = (along(pixel - 1), along(pixel));
+            let advance = |moved: f32, last: f32, target: f32| {
+                if moved == last {
+                    target
+                } else {
+                    moved + target - last
+                }
+            };
+            let ahead = Vector::new(
+                advance(moved.x, last.x, target.x),
+                advance(moved.y, last.y, target.y),
+            );
+
+            // try the full move first, then each axis on its own
+            let next = [
+                ahead,
+                Vector::new(ahead.x, moved.y),
+                Vector::new(moved.x, ahead.y),
+            ]
+            .into_iter()
+            .filter(|candidate| *candidate != moved)
+            .find(|candidate| self.is_free_at(entity_state, *candidate, &stuck_in));
+
+            match next {
+                Some(next) => moved = next,
+                None => break,
+            }
+        }
+
+        *entity_state.pos.as_mut().expect("entity has a position") += moved;
+        moved
+    }
+
+    /// Move the entity by `step`, handling the edges of the bounds according to its
+    /// boundary. Returns how far it moved, and the edges it tried to cross.
+    fn move_by(&self, entity_state: &mut EntityState, step: Vector) -> (Vector, Vec<Edge>) {
+        let bounds = self.bounds.expect("bounds should exist");
+
+        let start = entity_state.pos;
+        *entity_state.pos.as_mut().expect("entity has a position") += step;
+        let edges = entity_state.edges_outside(bounds);
+        entity_state.pos = start;
+
+        if entity_state.boundary == Boundary::Block && !edges.is_empty() {
+            return (Vector::default(), edges);
+        }
+
+        let moved = self.sweep(entity_state, step);
+        if entity_state.boundary == Boundary::Wrap {
+            entity_state.return_within(bounds);
+        }
+
+        (moved, edges)
+    }
+
+    /// Move the entity by its body's velocity, scaled by `speed`, bouncing off anything
+    /// in the way. Returns how far it moved.
+    fn integrate(&self, entity_state: &mut EntityState, speed: f32) -> Vector {
+        let Some(mut body) = entity_state
+            .entity
+            .as_mut()
+            .and_then(|entity| entity.body())
+            .cloned()
+        else {
+            return Vector::default();
+        };
+
+        let velocity = body.integrate();
+        let step = velocity * speed;
+        let (moved, edges) = self.move_by(entity_state, step);
+        let grounded = !self.is_free_at(entity_state, Vector::new(0.0, -1.0), &[]);
+        // a blocked move is cancelled outright, but only the axes crossing an edge
+        // were in the way
+        let unblocked = if entity_state.boundary == Boundary::Block {
+            let crossed = |axis: [Edge; 2]| edges.iter().any(|edge| axis.contains(edge));
+            Vector::new(
+                if crossed([Edge::Left, Edge::Right]) {
+                    moved.x
+                } else {
+                    step.x
+                },
+                if crossed([Edge::Bottom, Edge::Top]) {
+                    moved.y
+                } else {
+                    step.y
+                },
+            )
+        } else {
+            moved
+        };
+        body.resolve(step, unblocked, grounded);
+        if entity_state.boundary == Boundary::Bounce {
+            for edge in &edges {
+                body.bounce(*edge, velocity);
+            }
+        }
+
+        if let Some(entity_body) = entity_state
+            .entity
+            .as_mut()
+            .and_then(|entity| entity.body())
+        {
+            *entity_body = body;
+        }
+        entity_state.hit_edges(&edges);
+
+        moved
+    }
+
+    /// Report collisions between every overlapping pair of entities, once per pair
+    /// and in order of their IDs. Each side is told about the other if its collider
+    /// detects the other's, including when contact starts and ends.
+    fn collide(&mut self) {
+        let mut pairs = vec![];
+        for (index, entity_state) in self.entity_states.iter().enumerate() {
+            let entity_state = entity_state.borrow();
+            if entity_state.entity.is_none() {
+                continue;
+            }
+
+            // each pair is found once, from its lower index
+            for other_index in self
+                .grid
+                .query(entity_state.bounding_box())
+                .into_iter()
+                .filter(|other_index| *other_index > index)
             {
-                if entity_state.overlaps(&other_entity_state) {
-                    if let Some(entity) = entity_state.entity.as_mut() {
-                        if let Some(other_entity) = other_entity_state.entity.as_mut() {
-                            entity.collision(other_entity);
-                        }
-                    }
+                let other_entity_state = self.entity_states[other_index].borrow();
+                if other_entity_state.entity.is_some()
+                    && entity_state.overlaps(&other_entity_state)
+                    && (entity_state.collider.detects(&other_entity_state.collider)
+                        || other_entity_state.collider.detects(&entity_state.collider))
+                {
+                    pairs.push(if entity_state.id < other_entity_state.id {
+                        (
+                            (entity_state.id, index),
+                            (other_entity_state.id, other_index),
+                        )
+                    } else {
+                        (
+                            (other_entity_state.id, other_index),
+                            (entity_state.id, index),
+                        )
+                    });
                 }
             }
+        }
+        pairs.sort_unstable();
+
+        let mut contacts = BTreeSet::new();
+        for ((_, index), (_, other_index)) in pairs {
+            let mut entity_state = self.entity_states[index].borrow_mut();
+            let mut other_entity_state = self.entity_states[other_index].borrow_mut();
+
+            // the lower ID hears about the collision first
+            touch(
+                &mut entity_state,
+                &mut other_entity_state,
+                &self.contacts,
+                &mut contacts,
+            );
+            touch(
+                &mut other_entity_state,
+                &mut entity_state,
+                &self.contacts,
+                &mut contacts,
+            );
+        }
+
+        // contacts that ended, including with entities destroyed this tick
+        for &(id, other_id) in self.contacts.difference(&contacts) {
+            if !self.ids.is_alive(id) {
+                continue;
+            }
 
-            let update = if let Some(entity) = entity_state.entity.as_mut() {
-                entity.update(input)
-            } else {
-                Update::None
+            let mut entity_state = self.entity_states[self.lookup[id.index()]].borrow_mut();
+            if let Some(entity) = entity_state.entity.as_mut() {
+                entity.on_collision_exit(other_id);
+            }
+        }
+
+        self.contacts = contacts;
+    }
+
+    /// Index in `entity_states` of the entity with this ID, if it's been added.
+    /// Entities spawned this tick have live IDs before they're added, and may reuse
+    /// the slot of one that's gone, so the lookup alone can't be trusted.
+    fn find(&self, id: EntityId) -> Option<usize> {
+        if !self.ids.is_alive(id) {
+            return None;
+        }
+
+        let index = *self.lookup.get(id.index())?;
+        let entity_state = self.entity_states.get(index)?.try_borrow().ok()?;
+        (entity_state.id == id).then_some(index)
+    }
+
+    /// Deliver effects sent through the world, in the order they were sent.
+    /// Entities destroyed this tick miss out. Returns the effects for entities
+    /// spawned this tick, to deliver once they've been added.
+    fn deliver(&mut self, effects: Vec<(EntityId, Effect)>) -> Vec<(EntityId, Effect)> {
+        let mut pending = vec![];
+        for (id, effect) in effects {
+            let Some(index) = self.find(id) else {
+                if self.ids.is_alive(id) {
+                    pending.push((id, effect));
+                }
+                continue;
             };
 
-            match update {
-                Update::Action { step, rotate } => {
-                    let old_pos = entity_state.pos;
-                    *entity_state.pos.as_mut().expect("entity has a position") += step;
+            let entity_state = self.entity_states[index].get_mut();
+            let Some(entity) = entity_state.entity.as_mut() else {
+                continue;
+            };
 
-                    if !entity_state.within_bounds(self.bounds.expect("bounds should exist")) {
-                        entity_state.pos = old_pos;
-                    }
+            match &effect {
+                Effect::Status(status) => entity_state.statuses.apply(*status),
+                Effect::Damage(_) if entity_state.statuses.is_invulnerable() => continue,
+                _ => {}
+            }
+            entity.effect(effect);
+        }
+
+        pending
+    }
+
+    /// Update every entity for this tick, with `fps` used to count timers in seconds.
+    /// Returns the events published, once the entities have been told about them, and
+    /// the names of the scene's timers that went off.
+    fn update_entities(
+        &mut self,
+        input: Input,
+        fps: u32,
+        rng: &mut Rng,
+    ) -> Result<(Vec<event::Event>, Vec<&'static str>), GameError> {
+        let mut queued = Queued::default();
+        let fired = self.timers.tick(fps);
+        self.rebuild_broad_phase();
+
+        for (index, entity_state) in self.entity_states.iter().enumerate() {
+            let mut entity_state = entity_state.borrow_mut();
+            let id = entity_state.id;
+
+            let mut world = World::new(
+                &entity_state,
+                self.bounds.expect("bounds should exist"),
+                &self.entity_states,
+                &self.lookup,
+                &self.grid,
+                &self.index,
+                &mut self.ids,
+                &mut self.timers,
+                &mut queued,
+                rng,
+            );
+            if let Some(entity) = entity_state.entity.as_mut() {
+                for (_, name) in fired.iter().filter(|(owner, _)| *owner == Some(id)) {
+                    entity.on_timer(name, &mut world);
+                }
+            }
 
+            let stunned = entity_state.statuses.is_stunned();
+            let update = match entity_state.entity.as_mut() {
+                Some(entity) if !stunned => entity.update(input, &mut world),
+                _ => Update::None,
+            };
+            let speed = entity_state.statuses.speed();
+
+            let old_box = entity_state.bounding_box();
+            let mut moved = Vector::default();
+            match update {
+                Update::Action { step, rotate } => {
+                    let edges;
+                    (moved, edges) = self.move_by(&mut entity_state, step * speed);
+                    entity_state.hit_edges(&edges);
                     entity_state.rot += rotate;
                 }
 
@@ -277,14 +957,94 @@ impl State {
 
                 Update::None => {}
             }
+
+            // bodies keep moving whatever the entity did this tick
+            entity_state.velocity = moved + self.integrate(&mut entity_state, speed);
+            self.grid
+                .update(index, old_box, entity_state.bounding_box());
+
+            for kind in entity_state.statuses.tick() {
+                if let Some(entity) = entity_state.entity.as_mut() {
+                    entity.on_status_expired(kind);
+                }
+            }
         }
 
-        // some entities may have been destroyed
-        self.entity_states
-            .retain(|entity_state| entity_state.borrow().entity.is_some());
+        // collisions are resolved once everything has moved
+        self.collide();
+        let pending = self.deliver(queued.effects);
+        for event in &queued.events {
+            for entity_state in &mut self.entity_states {
+                if let Some(entity) = entity_state.get_mut().entity.as_mut() {
+                    entity.on_event(event);
+                }
+            }
+        }
 
-        Ok(())
+        // some entities may have been destroyed, along with their timers
+        let (ids, timers) = (&mut self.ids, &mut self.timers);
+        self.entity_states.retain(|entity_state| {
+            let entity_state = entity_state.borrow();
+            if entity_state.entity.is_none() {
+                ids.free(entity_state.id);
+                timers.cancel_all(entity_state.id);
+            }
+            entity_state.entity.is_some()
+        });
+        self.reindex();
+
+        // only add new entities once nothing is borrowed
+        self.add_spawns(queued.spawns);
+        // spawns dropped for being out of bounds miss out
+        self.deliver(pending);
+
+        let scene_timers = fired
+            .into_iter()
+            .filter_map(|(owner, name)| owner.is_none().then_some(name))
+            .collect();
+        Ok((queued.events, scene_timers))
+    }
+}
+
+/// Tell `detector` it's colliding with `other`, if its collider cares.
+/// Contacts not in `previous` are reported as just entered.
+fn touch(
+    detector: &mut EntityState,
+    other: &mut EntityState,
+    previous: &BTreeSet<(EntityId, EntityId)>,
+    contacts: &mut BTreeSet<(EntityId, EntityId)>,
+) {
+    if !detector.collider.detects(&other.collider) {
+        return;
+    }
+
+    let (Some(entity), Some(other_entity)) = (detector.entity.as_mut(), other.entity.as_mut())
+    else {
+        return;
+    };
+
+    if !previous.contains(&(detector.id, other.id)) {
+        entity.on_collision_enter(other_entity, other.id);
+    }
+    entity.collision(other_entity, other.id);
+    contacts.insert((detector.id, other.id));
+}
+
+/// Paint one sprite pixel at canvas position `x`/`y`.
+/// Returns `None` if the position is outside the canvas.
+fn paint_pixel(painter: &mut Painter, x: i32, y: i32, color: Color) -> Option<()> {
+    let (x_offset, y_offset) = painter.get_point(x as f64, y as f64)?;
+
+    // sprites will look squished unless scaling factor is accounted for
+    for x in 0..X_SCALE as usize {
+        for y in 0..Y_SCALE as usize {
+            if let (Some(x), Some(y)) = (x_offset.checked_sub(x), y_offset.checked_sub(y)) {
+                painter.paint(x, y, color);
+            }
+        }
     }
+
+    Some(())
 }
 
 impl Debug for State {
@@ -296,6 +1056,57 @@ impl Debug for State {
     }
 }
 
+/// Entities updating without a terminal, for benchmarking the engine.
+/// Only built with the `bench` feature.
+#[cfg(feature = "bench")]
+pub struct Bench {
+    state: State,
+    rng: Rng,
+}
+
+#[cfg(feature = "bench")]
+impl Bench {
+    /// Place the entities in a canvas `width` by `height` cells.
+    pub fn new<T>(entities: T, width: u16, height: u16) -> Result<Self, GameError>
+    where
+        T: IntoIterator<Item = Box<dyn Entity>>,
+    {
+        let mut state = State::new();
+        for entity in entities {
+            state.add_entity(entity);
+        }
+        // not `Rect::new`, which shrinks big areas
+        state.set_bounds(Rect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        });
+        state.set_starting_positions()?;
+
+        Ok(Self {
+            state,
+            rng: Rng::new(0),
+        })
+    }
+
+    /// Update every entity once, as the engine does every tick.
+    pub fn tick(&mut self) -> Result<(), GameError> {
+        self.state
+            .update_entities(Input::None, DEFAULT_FPS, &mut self.rng)?;
+        Ok(())
+    }
+
+    /// Entities still alive.
+    pub fn len(&self) -> usize {
+        self.state.entity_states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 struct TerminalHandle(Terminal<CrosstermBackend<io::Stdout>>);
 
 impl TerminalHandle {
@@ -349,11 +1160,25 @@ impl Drop for TerminalHandle {
 /// Game engine configuration builder.
 #[derive(Debug)]
 pub struct Engine {
-    state: State,
+    scenes: Vec<SceneState>,
+    // whether the bottom scene is a `Root` holding `starting_entities`
+    has_root: bool,
+    playing: Option<Playing>,
+    bounds: Rect,
     title: &'static str,
     ui_color: Color,
     bg_color: Color,
     fps: u32,
+    paused: bool,
+    slow_motion: u32,
+    inspecting: bool,
+    frame: u64,
+    hud: Vec<HudElement>,
+    draw_hook: Option<DrawHook>,
+    subscribers: Vec<Subscriber>,
+    rng: Rng,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
 }
 
 impl Default for Engine {
@@ -365,11 +1190,24 @@ impl Default for Engine {
 impl Engine {
     pub fn new() -> Self {
         Self {
-            state: State::new(),
+            scenes: vec![],
+            has_root: false,
+            playing: None,
+            bounds: Rect::default(),
             title: DEFAULT_TITLE,
             ui_color: DEFAULT_UI_COLOR.parse().unwrap(),
             bg_color: DEFAULT_BG_COLOR.parse().unwrap(),
             fps: DEFAULT_FPS,
+            paused: false,
+            slow_motion: 1,
+            inspecting: false,
+            frame: 0,
+            hud: vec![],
+            draw_hook: None,
+            subscribers: vec![],
+            rng: Rng::from_time(),
+            recorder: None,
+            replay: None,
         }
     }
 
@@ -390,7 +1228,7 @@ impl Engine {
         })
     }
     pub fn set_fps(self, fps: u32) -> Result<Self, GameError> {
-        if !FPS_BOUNDS.contains(&self.fps) {
+        if !FPS_BOUNDS.contains(&fps) {
             return Err(GameError::InvalidArg(format!(
                 "fps must be between {} and {}",
                 FPS_BOUNDS.start(),
@@ -401,22 +1239,119 @@ impl Engine {
         Ok(Self { fps, ..self })
     }
 
+    /// Only update the game every `slow_motion` frames, while still rendering every frame.
+    pub fn set_slow_motion(self, slow_motion: u32) -> Result<Self, GameError> {
+        if !SLOW_MOTION_BOUNDS.contains(&slow_motion) {
+            return Err(GameError::InvalidArg(format!(
+                "slow motion must be between {} and {}",
+                SLOW_MOTION_BOUNDS.start(),
+                SLOW_MOTION_BOUNDS.end()
+            )));
+        }
+
+        Ok(Self {
+            slow_motion,
+            ..self
+        })
+    }
+
+    /// Seed the random numbers, to play a run again exactly.
+    /// Without one, the seed comes from the time the engine was created.
+    pub fn set_seed(self, seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            ..self
+        }
+    }
+
+    /// Write the input of every tick to a file, along with the seed, to play the run
+    /// again later with `replay_from`.
+    pub fn record_to<P: AsRef<Path>>(self, path: P) -> Result<Self, GameError> {
+        Ok(Self {
+            recorder: Some(Recorder::create(path.as_ref())?),
+            ..self
+        })
+    }
+
+    /// Play a run recorded with `record_to` again, e.g. to reproduce a bug or as a
+    /// demo. Its seed and FPS are used instead of the engine's, and the canvas must
+    /// be the same size. The keyboard takes over once the recording runs out.
+    pub fn replay_from<P: AsRef<Path>>(self, path: P) -> Result<Self, GameError> {
+        Ok(Self {
+            replay: Some(Replay::load(path.as_ref())?),
+            ..self
+        })
+    }
+
+    /// Entities for a scene at the bottom of the stack, which is the whole game if
+    /// no scenes are pushed with `starting_scene`. Popping it ends the game.
     pub fn starting_entities<T>(mut self, entities: T) -> Self
     where
         T: IntoIterator<Item = Box<dyn Entity>>,
     {
+        if !self.has_root {
+            self.scenes.insert(0, SceneState::new(Box::new(Root)));
+            self.has_root = true;
+        }
+
         for entity in entities {
-            self.state.add_entity(entity);
+            self.scenes[0].state.add_entity(entity);
         }
         self
     }
 
+    /// Push a scene onto the stack before the game starts, e.g. a title menu.
+    pub fn starting_scene(mut self, scene: Box<dyn Scene>) -> Self {
+        self.scenes.push(SceneState::new(scene));
+        self
+    }
+
+    /// Add a HUD widget around or over the canvas.
+    pub fn hud_element(mut self, element: HudElement) -> Self {
+        self.hud.push(element);
+        self
+    }
+
+    /// Draw on top of the game every frame, relative to the bottom-left of the canvas.
+    pub fn on_draw<F>(self, draw_hook: F) -> Self
+    where
+        F: Fn(&mut Draw) + 'static,
+    {
+        Self {
+            draw_hook: Some(DrawHook(Box::new(draw_hook))),
+            ..self
+        }
+    }
+
+    /// React to every event of type `T` published by entities, e.g. to keep score.
+    pub fn subscribe<T, F>(mut self, mut subscriber: F) -> Self
+    where
+        T: Any,
+        F: FnMut(&T) + 'static,
+    {
+        self.subscribers
+            .push(Subscriber(Box::new(move |event: &event::Event| {
+                if let Some(event) = event.downcast_ref() {
+                    subscriber(event);
+                }
+            })));
+        self
+    }
+
     fn get_canvas<F>(&self) -> Canvas<'_, F>
     where
         F: Fn(&mut Context),
     {
+        let status = if self.paused {
+            " (paused)".to_string()
+        } else if self.slow_motion > 1 {
+            format!(" (1/{} speed)", self.slow_motion)
+        } else {
+            String::new()
+        };
+
         let game_border = Block::default()
-            .title(format!(" {} ", self.title))
+            .title(format!(" {}{} ", self.title, status))
             .title_style(
                 Style::default()
                     .add_modifier(Modifier::BOLD)
@@ -432,58 +1367,272 @@ impl Engine {
             .block(game_border)
             .background_color(self.bg_color)
             .marker(Marker::Block)
-            .x_bounds([0.0, self.state.bounds.unwrap().width as f64])
-            .y_bounds([0.0, self.state.bounds.unwrap().height as f64]);
+            .x_bounds([0.0, self.bounds.width as f64])
+            .y_bounds([0.0, self.bounds.height as f64]);
 
         canvas
     }
 
+    /// Update the top scene, or the transition between scenes if one is playing.
+    /// Returns false once the game should quit.
+    fn update(&mut self, input: Input) -> Result<bool, GameError> {
+        if let Some(playing) = self.playing.as_mut() {
+            let change = playing.advance();
+            if playing.is_finished() {
+                self.playing = None;
+            }
+
+            return match change {
+                Some(change) => self.change_scene(change),
+                None => Ok(true),
+            };
+        }
+
+        let top = self.scenes.last_mut().expect("there is always a scene");
+        let change = top.scene.update(input);
+
+        let transition = match &change {
+            SceneChange::None => {
+                let (events, timers) = top.state.update_entities(input, self.fps, &mut self.rng)?;
+                for name in timers {
+                    debug!(name, "scene timer went off");
+                    let entities = top.scene.on_timer(name, &mut self.rng);
+                    if entities.is_empty() {
+                        continue;
+                    }
+
+                    for entity in entities {
+                        top.state.add_entity(entity);
+                    }
+                    top.state.set_starting_positions()?;
+                }
+
+                for event in events {
+                    top.scene.on_event(&event);
+                    for Subscriber(subscriber) in &mut self.subscribers {
+                        subscriber(&event);
+                    }
+                }
+                return Ok(true);
+            }
+            SceneChange::Push(scene) | SceneChange::Replace(scene) => scene.transition(),
+            SceneChange::Pop | SceneChange::Quit => top.scene.transition(),
+        };
+
+        if transition.ticks() == 0 {
+            self.change_scene(change)
+        } else {
+            debug!(?transition, "scene transition started");
+            self.playing = Some(Playing::new(transition, change));
+            Ok(true)
+        }
+    }
+
+    /// Update the game for a tick, taking the input from the replay while there is one,
+    /// and recording it if asked to.
+    fn tick(&mut self, input: Input) -> Result<bool, GameError> {
+        let input = match self.replay.as_mut().map(Replay::next) {
+            Some(Some(recorded)) => recorded,
+            Some(None) => {
+                debug!("replay finished");
+                self.replay = None;
+                input
+            }
+            None => input,
+        };
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(input)?;
+        }
+
+        self.update(input)
+    }
+
+    /// Handle the debugging controls, then update the game unless it's paused or
+    /// slowed down. The input is kept until an update consumes it.
+    /// Returns false once the game should quit.
+    fn debug_update(&mut self, key: &mut Key) -> Result<bool, GameError> {
+        self.frame += 1;
+
+        match *key {
+            Key::Game(Input::Quit) => return Ok(false),
+
+            Key::Pause => {
+                self.paused = !self.paused;
+                debug!(paused = self.paused, "pause toggled");
+            }
+
+            Key::Step => {
+                if self.paused {
+                    debug!("stepping one tick");
+                    *key = Key::Game(Input::None);
+                    return self.tick(Input::None);
+                }
+            }
+
+            Key::Inspect => {
+                self.inspecting = !self.inspecting;
+                debug!(inspecting = self.inspecting, "debug overlay toggled");
+            }
+
+            Key::SlowMotion => {
+                // the next slower speed, from wherever `set_slow_motion` started it
+                self.slow_motion = SLOW_MOTION_SPEEDS
+                    .into_iter()
+                    .find(|speed| *speed > self.slow_motion)
+                    .unwrap_or(SLOW_MOTION_SPEEDS[0]);
+                debug!(slow_motion = self.slow_motion, "slow motion changed");
+            }
+
+            Key::Game(input) => {
+                if self.paused || self.frame % self.slow_motion as u64 != 0 {
+                    return Ok(true);
+                }
+
+                let running = self.tick(input)?;
+                *key = Key::Game(Input::None);
+                return Ok(running);
+            }
+        }
+
+        *key = Key::Game(Input::None);
+        Ok(true)
+    }
+
+    /// Apply a change to the scene stack. Returns false once the game should quit.
+    fn change_scene(&mut self, change: SceneChange) -> Result<bool, GameError> {
+        match change {
+            SceneChange::None => {}
+
+            SceneChange::Push(scene) => {
+                let mut scene_state = SceneState::new(scene);
+                scene_state.start(self.bounds)?;
+                self.scenes.push(scene_state);
+            }
+
+            SceneChange::Pop => {
+                self.scenes.pop();
+            }
+
+            SceneChange::Replace(scene) => {
+                self.scenes.pop();
+                let mut scene_state = SceneState::new(scene);
+                scene_state.start(self.bounds)?;
+                self.scenes.push(scene_state);
+            }
+
+            SceneChange::Quit => return Ok(false),
+        }
+        debug!(len = self.scenes.len(), "scene stack changed");
+
+        // popping the last scene ends the game
+        Ok(!self.scenes.is_empty())
+    }
+
     /// Begin rendering the game using the provided `config` settings.
     #[instrument]
     pub fn init(mut self) -> Result<(), GameError> {
         let mut handle = TerminalHandle::new()?;
         let terminal = &mut handle.0;
 
-        let sleep_duration = Duration::from_secs_f32(1.0 / self.fps as f32);
+        // the canvas gets whatever space the HUD leaves over
+        let (canvas_area, _) = hud::layout(&self.hud, terminal.size()?);
+        self.bounds = Rect::new(0, 0, canvas_area.width, canvas_area.height);
+        let size = (self.bounds.width, self.bounds.height);
+
+        if let Some(replay) = &self.replay {
+            // entities are placed relative to the canvas, so any other size plays out differently
+            if replay.size != size {
+                return Err(GameError::InvalidReplay(format!(
+                    "recorded on a {}x{} canvas, but this one is {}x{}",
+                    replay.size.0, replay.size.1, size.0, size.1
+                )));
+            }
 
-        self.state.set_bounds(terminal.size()?);
-        self.state.set_starting_positions()?;
+            self.rng = Rng::new(replay.seed);
+            self.fps = replay.fps;
+            debug!(?replay, "replaying recording");
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.start(self.rng.seed(), self.fps, size)?;
+        }
 
-        let input = Arc::new(Mutex::new(Input::None));
+        let sleep_duration = Duration::from_secs_f32(1.0 / self.fps as f32);
+        debug!(seed = self.rng.seed(), "random numbers seeded");
+        // a game without scenes or entities still runs, empty, until it's quit
+        if self.scenes.is_empty() {
+            self.scenes.push(SceneState::new(Box::new(Root)));
+        }
+        for scene_state in &mut self.scenes {
+            scene_state.start(self.bounds)?;
+        }
+
+        let key = Arc::new(Mutex::new(Key::Game(Input::None)));
 
         // separate thread reads keyboard and updates the current input
         debug!("creating input reading thread");
         {
-            let input = input.clone();
+            let key = key.clone();
 
             thread::spawn(move || loop {
-                *input.lock().unwrap() = read_input();
+                *key.lock().unwrap() = read_input();
             });
         }
 
         let maybe_error = Cell::default();
         loop {
             {
-                let mut input = input.lock().expect("not poisoned");
-                if *input == Input::Quit {
+                let mut key = key.lock().expect("not poisoned");
+                if !self.debug_update(&mut key)? {
                     return Ok(());
                 }
-                self.state.update_entities(*input)?;
-                *input = Input::None;
             }
 
             terminal.draw(|frame| {
+                let (canvas_area, hud_areas) = hud::layout(&self.hud, frame.size());
+
+                // overlays keep the scenes beneath them visible
+                let first_visible = self
+                    .scenes
+                    .iter()
+                    .rposition(|scene_state| !scene_state.scene.is_overlay())
+                    .unwrap_or(0);
+
                 frame.render_widget(
                     self.get_canvas().paint(|ctx| {
-                        // render the entities, and hold onto any errors
-                        if let Err(error) = self.state.render_entities(ctx) {
-                            maybe_error.set(Some(error));
+                        for scene_state in &self.scenes[first_visible..] {
+                            // render the entities, and hold onto any errors
+                            if let Err(error) = scene_state
+                                .state
+                                .render_entities(ctx, self.inspecting.then_some(self.ui_color))
+                            {
+                                maybe_error.set(Some(error));
+                            }
+
+                            let mut draw = Draw::at(0, 0);
+                            scene_state.scene.draw(&mut draw);
+                            draw.render(ctx);
+                            ctx.layer();
+                        }
+
+                        if let Some(DrawHook(draw_hook)) = &self.draw_hook {
+                            let mut draw = Draw::at(0, 0);
+                            draw_hook(&mut draw);
+                            draw.render(ctx);
                         }
 
                         ctx.layer();
                     }),
-                    frame.size(),
+                    canvas_area,
                 );
+
+                for (element, area) in self.hud.iter().zip(hud_areas) {
+                    element.render(frame, area, self.ui_color, self.bg_color);
+                }
+
+                if let Some(playing) = &self.playing {
+                    frame.render_widget(playing.cover(), frame.size());
+                }
             })?;
             if let Some(error) = maybe_error.take() {
                 return Err(error);
@@ -494,23 +1643,962 @@ impl Engine {
     }
 }
 
-fn read_input() -> Input {
+/// Key pressed by the player: either input for the game, or one of the engine's own
+/// debugging controls, which entities never see.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Key {
+    Game(Input),
+    /// Freeze or unfreeze all updates.
+    Pause,
+    /// Run exactly one tick while paused.
+    Step,
+    /// Cycle through the slow motion speeds.
+    SlowMotion,
+    /// Show or hide each entity's `debug_state` and statuses.
+    Inspect,
+}
+
+fn read_input() -> Key {
     let Event::Key(key) = crossterm::event::read().expect("reading event") else {
-        return Input::None;
+        return Key::Game(Input::None);
     };
 
     // quit the game if ctrl+c or q pressed
     if key.code == KeyCode::Char('q')
         || (key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c'))
     {
-        return Input::Quit;
+        return Key::Game(Input::Quit);
     }
 
-    match key.code {
+    let input = match key.code {
         KeyCode::Up | KeyCode::Char('w') => Input::Up,
         KeyCode::Down | KeyCode::Char('s') => Input::Down,
         KeyCode::Left | KeyCode::Char('a') => Input::Left,
         KeyCode::Right | KeyCode::Char('d') => Input::Right,
+        KeyCode::Char(' ') => Input::Action,
+        KeyCode::Esc => Input::Escape,
+        KeyCode::Char('p') => return Key::Pause,
+        KeyCode::Char('n') => return Key::Step,
+        KeyCode::Char('z') => return Key::SlowMotion,
+        KeyCode::Char('i') => return Key::Inspect,
         _ => Input::None,
+    };
+
+    Key::Game(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::ColliderKind;
+    use physics::Body;
+    use status::{Status, StatusKind};
+    use std::collections::VecDeque;
+    use timer::{Delay, Timer};
+    use world::Seen;
+
+    const BOUNDS: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 100,
+        height: 50,
+    };
+
+    fn sprite() -> Rc<Sprite> {
+        Rc::new(Sprite::filled(4, 4, (255, 255, 255)))
+    }
+
+    fn state<T>(entities: T) -> State
+    where
+        T: IntoIterator<Item = Box<dyn Entity>>,
+    {
+        let mut state = State::new();
+        for entity in entities {
+            state.add_entity(entity);
+        }
+        state.set_bounds(BOUNDS);
+        state.set_starting_positions().unwrap();
+        state
+    }
+
+    fn tick(state: &mut State) {
+        state
+            .update_entities(Input::None, 10, &mut Rng::new(0))
+            .unwrap();
+    }
+
+    /// Adds up the damage it takes.
+    #[derive(Debug)]
+    struct Target(Rc<Sprite>, Rc<Cell<i32>>);
+
+    impl Entity for Target {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.5, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, _world: &mut World) -> Update {
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, effect: Effect) {
+            if let Effect::Damage(damage) = effect {
+                self.1.set(self.1.get() + damage);
+            }
+        }
+    }
+
+    /// Spawns a `Target` after waiting `ticks`, and damages it straight away.
+    #[derive(Debug)]
+    struct Spawner {
+        sprite: Rc<Sprite>,
+        ticks: u32,
+        damage: Rc<Cell<i32>>,
+    }
+
+    impl Entity for Spawner {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.2, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            if self.ticks == 0 {
+                let target = Target(self.sprite.clone(), self.damage.clone());
+                let id = world.spawn(Box::new(target), Vector::new(10.0, 0.0));
+                world.send(id, Effect::Damage(3));
+            }
+            self.ticks = self.ticks.wrapping_sub(1);
+
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    /// Destroys itself on its first update.
+    #[derive(Debug)]
+    struct Doomed(Rc<Sprite>);
+
+    impl Entity for Doomed {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.8, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, _world: &mut World) -> Update {
+            Update::Destroy
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    /// Solid entity moving by `step` every tick.
+    #[derive(Debug)]
+    struct Mover {
+        sprite: Rc<Sprite>,
+        start: (f32, f32),
+        step: Vector,
+    }
+
+    impl Mover {
+        fn boxed(start: (f32, f32), step: Vector) -> Box<dyn Entity> {
+            Box::new(Self {
+                sprite: sprite(),
+                start,
+                step,
+            })
+        }
+    }
+
+    impl Entity for Mover {
+        fn start_pos(&self) -> (f32, f32) {
+            self.start
+        }
+
+        fn tags(&self) -> &[&'static str] {
+            &["mover"]
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, _world: &mut World) -> Update {
+            Update::Action {
+                step: self.step,
+                rotate: Rotation::Zero,
+            }
+        }
+
+        fn collider(&self) -> Collider {
+            Collider::new(1, 1, ColliderKind::Solid)
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    fn positions(state: &mut State) -> Vec<(f32, f32)> {
+        state
+            .entity_states
+            .iter_mut()
+            .map(|entity_state| {
+                let pos = entity_state.get_mut().pos.unwrap();
+                (pos.x, pos.y)
+            })
+            .collect()
+    }
+
+    fn overlapping(state: &State) -> bool {
+        state.entity_states[0]
+            .borrow()
+            .overlaps(&state.entity_states[1].borrow())
+    }
+
+    /// Thrown against the bounds, noting its velocity every tick.
+    #[derive(Debug)]
+    struct Thrown {
+        sprite: Rc<Sprite>,
+        body: Body,
+        velocity: Rc<Cell<Vector>>,
+    }
+
+    impl Entity for Thrown {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.0, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, _world: &mut World) -> Update {
+            self.velocity.set(self.body.velocity);
+            Update::None
+        }
+
+        fn body(&mut self) -> Option<&mut Body> {
+            Some(&mut self.body)
+        }
+
+        fn boundary(&self) -> Boundary {
+            Boundary::Block
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn blocked_bodies_only_bounce_off_the_edge_they_hit() {
+        let velocity = Rc::new(Cell::new(Vector::default()));
+        let mut state = state([Box::new(Thrown {
+            sprite: sprite(),
+            body: Body::new(Vector::new(-2.0, 1.0)).with_restitution(1.0),
+            velocity: velocity.clone(),
+        }) as Box<dyn Entity>]);
+
+        tick(&mut state);
+        tick(&mut state);
+        assert_eq!(velocity.get(), Vector::new(2.0, 1.0));
+    }
+
+    /// Contact events heard by each `Toucher`: its name, the event and the other's ID.
+    type Touches = Rc<RefCell<Vec<(&'static str, &'static str, EntityId)>>>;
+
+    /// Trigger that follows a script of updates, noting every contact event.
+    #[derive(Debug)]
+    struct Toucher {
+        sprite: Rc<Sprite>,
+        name: &'static str,
+        script: VecDeque<Update>,
+        touches: Touches,
+    }
+
+    impl Entity for Toucher {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.5, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, _world: &mut World) -> Update {
+            self.script.pop_front().unwrap_or_default()
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, other_id: EntityId) {
+            self.touches
+                .borrow_mut()
+                .push((self.name, "stay", other_id));
+        }
+
+        fn on_collision_enter(&mut self, _other: &mut Box<dyn Entity>, other_id: EntityId) {
+            self.touches
+                .borrow_mut()
+                .push((self.name, "enter", other_id));
+        }
+
+        fn on_collision_exit(&mut self, other_id: EntityId) {
+            self.touches
+                .borrow_mut()
+                .push((self.name, "exit", other_id));
+        }
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    /// Overlapping touchers following the given scripts, and their IDs.
+    fn touchers<const N: usize>(
+        scripts: [(&'static str, Vec<Update>); N],
+        touches: &Touches,
+    ) -> (State, Vec<EntityId>) {
+        let mut state = State::new();
+        let ids = scripts
+            .into_iter()
+            .map(|(name, script)| {
+                state.add_entity(Box::new(Toucher {
+                    sprite: sprite(),
+                    name,
+                    script: script.into(),
+                    touches: touches.clone(),
+                }))
+            })
+            .collect();
+        state.set_bounds(BOUNDS);
+        state.set_starting_positions().unwrap();
+        (state, ids)
+    }
+
+    /// Drains the contact events heard during one tick.
+    fn touches_in_tick(
+        state: &mut State,
+        touches: &Touches,
+    ) -> Vec<(&'static str, &'static str, EntityId)> {
+        tick(state);
+        touches.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn contacts_enter_once_then_stay_until_they_separate() {
+        let touches = Touches::default();
+        let away = Update::Action {
+            step: Vector::new(20.0, 0.0),
+            rotate: Rotation::Zero,
+        };
+        let (mut state, ids) = touchers(
+            [("a", vec![]), ("b", vec![Update::None, Update::None, away])],
+            &touches,
+        );
+        let (a, b) = (ids[0], ids[1]);
+
+        assert_eq!(
+            touches_in_tick(&mut state, &touches),
+            [
+                ("a", "enter", b),
+                ("a", "stay", b),
+                ("b", "enter", a),
+                ("b", "stay", a)
+            ]
+        );
+        assert_eq!(
+            touches_in_tick(&mut state, &touches),
+            [("a", "stay", b), ("b", "stay", a)]
+        );
+        assert_eq!(
+            touches_in_tick(&mut state, &touches),
+            [("a", "exit", b), ("b", "exit", a)]
+        );
+        assert_eq!(touches_in_tick(&mut state, &touches), []);
+    }
+
+    #[test]
+    fn contacts_exit_when_the_other_is_destroyed() {
+        let touches = Touches::default();
+        let (mut state, ids) = touchers(
+            [("a", vec![]), ("b", vec![Update::None, Update::Destroy])],
+            &touches,
+        );
+
+        touches_in_tick(&mut state, &touches);
+        assert_eq!(
+            touches_in_tick(&mut state, &touches),
+            [("a", "exit", ids[1])]
+        );
+        assert_eq!(touches_in_tick(&mut state, &touches), []);
+    }
+
+    #[test]
+    fn each_pair_is_reported_once_in_order_of_ids() {
+        let touches = Touches::default();
+        let (mut state, ids) = touchers(
+            [("a", vec![Update::Destroy]), ("b", vec![]), ("c", vec![])],
+            &touches,
+        );
+        touches_in_tick(&mut state, &touches);
+
+        // the new entity reuses the destroyed one's ID index, so it has the lowest ID
+        // despite being stored last
+        let d = state.add_entity(Box::new(Toucher {
+            sprite: sprite(),
+            name: "d",
+            script: VecDeque::new(),
+            touches: touches.clone(),
+        }));
+        state.set_starting_positions().unwrap();
+        let (b, c) = (ids[1], ids[2]);
+        assert!(d < b);
+        assert_eq!(state.lookup[d.index()], 2);
+
+        let stays: Vec<_> = touches_in_tick(&mut state, &touches)
+            .into_iter()
+            .filter(|(_, event, _)| *event == "stay")
+            .collect();
+        assert_eq!(
+            stays,
+            [
+                ("d", "stay", b),
+                ("b", "stay", d),
+                ("d", "stay", c),
+                ("c", "stay", d),
+                ("b", "stay", c),
+                ("c", "stay", b),
+            ]
+        );
+    }
+
+    /// Wraps around the bounds, spawning another like it `spawn` away on its first update.
+    #[derive(Debug)]
+    struct Wrapping {
+        sprite: Rc<Sprite>,
+        start: (f32, f32),
+        spawn: Option<Vector>,
+    }
+
+    impl Entity for Wrapping {
+        fn start_pos(&self) -> (f32, f32) {
+            self.start
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            if let Some(offset) = self.spawn.take() {
+                let spawn = Wrapping {
+                    sprite: self.sprite.clone(),
+                    start: (0.5, 0.5),
+                    spawn: None,
+                };
+                world.spawn(Box::new(spawn), offset);
+            }
+            Update::None
+        }
+
+        fn boundary(&self) -> Boundary {
+            Boundary::Wrap
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn wrapping_entities_are_placed_at_the_nearest_edge() {
+        let mut state = state([Box::new(Wrapping {
+            sprite: sprite(),
+            start: (0.0, 0.5),
+            spawn: Some(Vector::new(1000.0, 0.0)),
+        }) as Box<dyn Entity>]);
+        assert_eq!(positions(&mut state)[0].0, 2.0);
+
+        tick(&mut state);
+        assert_eq!(positions(&mut state)[1].0, 94.0);
+    }
+
+    /// What the `Looker` found: the nearest mover, searched for by type, by tag and by
+    /// radius, and how many were tagged.
+    type Found = (Option<Seen>, Option<Seen>, Option<Seen>, usize);
+
+    /// Looks for movers every tick.
+    #[derive(Debug)]
+    struct Looker(Rc<Sprite>, Rc<RefCell<Vec<Found>>>);
+
+    impl Entity for Looker {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.0, 0.0)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            let is_mover = |entity: &dyn Entity| entity.is::<Mover>();
+            self.1.borrow_mut().push((
+                world.nearest(is_mover),
+                world.nearest_tagged("mover"),
+                world.within_radius(1000.0, is_mover).first().copied(),
+                world.tagged("mover").len(),
+            ));
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn queries_find_the_nearest_match_near_or_far() {
+        let found = Rc::new(RefCell::new(vec![]));
+        let mut state = state([
+            Box::new(Looker(sprite(), found.clone())) as Box<dyn Entity>,
+            Mover::boxed((1.0, 1.0), Vector::default()),
+            Mover::boxed((0.6, 0.7), Vector::default()),
+        ]);
+
+        tick(&mut state);
+        let (nearest, nearest_tagged, within_radius, tagged) = found.borrow()[0];
+        assert!(nearest.is_some());
+        assert_eq!(nearest, within_radius);
+        assert_eq!(nearest, nearest_tagged);
+        assert_eq!(tagged, 2);
+
+        // once something's close by, the search stops early and still finds it
+        state.entity_states[2].get_mut().pos = Some(Position { x: 6.0, y: 3.0 });
+        tick(&mut state);
+        let (nearest, _, within_radius, _) = found.borrow()[1];
+        assert_eq!(nearest, within_radius);
+        assert!(nearest.is_some_and(|seen| seen.distance < 16.0));
+    }
+
+    /// Looks at a `Target` every tick, noting the damage it's taken.
+    #[derive(Debug)]
+    struct Peeker(Rc<Sprite>, EntityId, Rc<RefCell<Vec<Option<i32>>>>);
+
+    impl Entity for Peeker {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.0, 0.0)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            assert!(world.get::<Peeker>(self.1).is_none());
+            let damage = world.get::<Target>(self.1).map(|target| target.1.get());
+            self.2.borrow_mut().push(damage);
+
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn entities_can_be_read_by_id_as_their_type() {
+        let (damage, seen) = (Rc::new(Cell::new(3)), Rc::new(RefCell::new(vec![])));
+        let mut state = State::new();
+        let target = state.add_entity(Box::new(Target(sprite(), damage)));
+        let peeker = state.add_entity(Box::new(Peeker(sprite(), target, seen.clone())));
+        state.set_bounds(BOUNDS);
+        state.set_starting_positions().unwrap();
+
+        tick(&mut state);
+        state.entity_states[0].get_mut().entity = None;
+        tick(&mut state);
+
+        assert_eq!(*seen.borrow(), [Some(3), None]);
+        assert!(state.find(peeker).is_some());
+    }
+
+    /// Slows itself down on its first tick, noting whether it's slowed every tick.
+    #[derive(Debug)]
+    struct Sluggish(Rc<Sprite>, Rc<RefCell<Vec<bool>>>);
+
+    impl Entity for Sluggish {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.5, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            if self.1.borrow().is_empty() {
+                let slow = Status::new(StatusKind::Slow(0.5), 3);
+                world.send(world.id(), Effect::Status(slow));
+            }
+            // slows of any speed count
+            self.1
+                .borrow_mut()
+                .push(world.has_status(StatusKind::Slow(1.0)));
+
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn entities_can_check_their_statuses() {
+        let slowed = Rc::new(RefCell::new(vec![]));
+        let mut state = state([Box::new(Sluggish(sprite(), slowed.clone())) as Box<dyn Entity>]);
+        for _ in 0..5 {
+            tick(&mut state);
+        }
+
+        assert_eq!(*slowed.borrow(), [false, true, true, true, false]);
+    }
+
+    /// Stuns itself and makes itself invulnerable on its first update, while sending
+    /// itself damage. Sends itself more damage on its second update.
+    #[derive(Debug)]
+    struct Dazed {
+        sprite: Rc<Sprite>,
+        updates: Rc<Cell<u32>>,
+        damage: Rc<Cell<i32>>,
+    }
+
+    impl Entity for Dazed {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.5, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            self.updates.set(self.updates.get() + 1);
+            match self.updates.get() {
+                1 => {
+                    for kind in [StatusKind::Stun, StatusKind::Invulnerable] {
+                        world.send(world.id(), Effect::Status(Status::new(kind, 2)));
+                    }
+                    world.send(world.id(), Effect::Damage(5));
+                }
+                2 => world.send(world.id(), Effect::Damage(1)),
+                _ => {}
+            }
+
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, effect: Effect) {
+            if let Effect::Damage(damage) = effect {
+                self.damage.set(self.damage.get() + damage);
+            }
+        }
+    }
+
+    #[test]
+    fn stuns_skip_updates_and_invulnerability_drops_sent_damage() {
+        let (updates, damage) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let mut state = state([Box::new(Dazed {
+            sprite: sprite(),
+            updates: updates.clone(),
+            damage: damage.clone(),
+        }) as Box<dyn Entity>]);
+
+        for _ in 0..3 {
+            tick(&mut state);
+        }
+        assert_eq!((updates.get(), damage.get()), (1, 0));
+
+        tick(&mut state);
+        assert_eq!((updates.get(), damage.get()), (2, 1));
+    }
+
+    /// Starts a repeating timer, then destroys itself once it's gone off twice.
+    #[derive(Debug)]
+    struct Ticking(Rc<Sprite>, Rc<Cell<u32>>);
+
+    impl Entity for Ticking {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.5, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            match self.1.get() {
+                0 if !world.has_timer("tick") => {
+                    world.start_timer(Timer::every("tick", Delay::Ticks(1)));
+                    Update::None
+                }
+                2 => Update::Destroy,
+                _ => Update::None,
+            }
+        }
+
+        fn on_timer(&mut self, _name: &'static str, _world: &mut World) {
+            self.1.set(self.1.get() + 1);
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn timers_are_cancelled_with_their_owner() {
+        let fired = Rc::new(Cell::new(0));
+        let mut state = state([Box::new(Ticking(sprite(), fired.clone())) as Box<dyn Entity>]);
+        for _ in 0..5 {
+            tick(&mut state);
+        }
+
+        assert_eq!(fired.get(), 2);
+        assert!(state.entity_states.is_empty());
+        assert!(state.timers.tick(10).is_empty());
+    }
+
+    /// Who heard which event, in order.
+    type Heard = Rc<RefCell<Vec<String>>>;
+
+    fn hear(heard: &Heard, who: &str, event: &event::Event) {
+        let what = match (event.downcast_ref::<u32>(), event.downcast_ref::<&str>()) {
+            (Some(number), _) => number.to_string(),
+            (_, Some(text)) => text.to_string(),
+            _ => "?".to_string(),
+        };
+        heard.borrow_mut().push(format!("{who} {what}"));
+    }
+
+    /// Publishes a number and then some text on its first update.
+    #[derive(Debug)]
+    struct Publisher(Rc<Sprite>, Heard, bool);
+
+    impl Entity for Publisher {
+        fn start_pos(&self) -> (f32, f32) {
+            (0.5, 0.5)
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.0
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            if !self.2 {
+                world.publish(1u32);
+                world.publish("two");
+                self.2 = true;
+            }
+            Update::None
+        }
+
+        fn on_event(&mut self, event: &event::Event) {
+            hear(&self.1, "entity", event);
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    /// Starts with a `Publisher`, noting the events it hears.
+    #[derive(Debug)]
+    struct Stage(Heard);
+
+    impl Scene for Stage {
+        fn starting_entities(&mut self) -> Vec<Box<dyn Entity>> {
+            vec![Box::new(Publisher(sprite(), self.0.clone(), false))]
+        }
+
+        fn on_event(&mut self, event: &event::Event) {
+            hear(&self.0, "scene", event);
+        }
+    }
+
+    #[test]
+    fn events_reach_entities_then_the_scene_then_subscribers_of_their_type() {
+        let heard = Heard::default();
+        let subscribed = heard.clone();
+        let mut engine = Engine::new().subscribe(move |number: &u32| {
+            subscribed.borrow_mut().push(format!("subscriber {number}"));
+        });
+        engine.bounds = BOUNDS;
+        engine
+            .change_scene(SceneChange::Push(Box::new(Stage(heard.clone()))))
+            .unwrap();
+
+        assert!(engine.update(Input::None).unwrap());
+        assert_eq!(
+            *heard.borrow(),
+            [
+                "entity 1",
+                "entity two",
+                "scene 1",
+                "subscriber 1",
+                "scene two"
+            ]
+        );
+    }
+
+    #[derive(Debug)]
+    struct Menu;
+
+    impl Scene for Menu {}
+
+    #[test]
+    fn fps_outside_the_bounds_is_rejected() {
+        assert!(Engine::new().set_fps(60).is_err());
+        assert!(Engine::new().set_fps(0).is_err());
+        assert_eq!(Engine::new().set_fps(30).unwrap().fps, 30);
+    }
+
+    #[test]
+    fn the_slow_motion_key_stays_within_bounds() {
+        let mut engine = Engine::new().set_slow_motion(3).unwrap();
+        let speeds: Vec<_> = (0..4)
+            .map(|_| {
+                engine.debug_update(&mut Key::SlowMotion).unwrap();
+                engine.slow_motion
+            })
+            .collect();
+
+        assert_eq!(speeds, [4, 8, 1, 2]);
+    }
+
+    #[test]
+    fn popping_the_last_scene_ends_the_game() {
+        let mut engine = Engine::new().starting_scene(Box::new(Menu));
+
+        assert!(!engine.change_scene(SceneChange::Pop).unwrap());
+    }
+
+    #[test]
+    fn popping_down_to_the_starting_entities_keeps_playing() {
+        let mut engine = Engine::new()
+            .starting_scene(Box::new(Menu))
+            .starting_entities([Mover::boxed((0.5, 0.5), Vector::default())]);
+
+        assert!(engine.change_scene(SceneChange::Pop).unwrap());
+        assert_eq!(engine.scenes[0].state.entity_states.len(), 1);
+        assert!(!engine.change_scene(SceneChange::Pop).unwrap());
+    }
+
+    /// Solid that stays put, `width` by `height` pixels.
+    fn wall(width: u32, height: u32) -> Box<dyn Entity> {
+        Box::new(Mover {
+            sprite: Rc::new(Sprite::filled(width, height, (255, 255, 255))),
+            start: (0.5, 0.5),
+            step: Vector::default(),
+        })
+    }
+
+    /// Moves a 4x4 mover from the left of a 3x20 wall by `step`, returning where it ends up.
+    fn move_at_wall(step: Vector) -> (f32, f32) {
+        let mut state = state([Mover::boxed((0.5, 0.5), step), wall(3, 20)]);
+        state.entity_states[0].get_mut().pos = Some(Position { x: 16.0, y: 20.0 });
+        state.entity_states[1].get_mut().pos = Some(Position { x: 30.0, y: 10.0 });
+
+        tick(&mut state);
+        assert!(!overlapping(&state));
+        positions(&mut state)[0]
+    }
+
+    #[test]
+    fn solids_block_each_other() {
+        assert_eq!(move_at_wall(Vector::new(20.0, 0.0)), (24.0, 20.0));
+    }
+
+    #[test]
+    fn solids_slide_along_walls() {
+        assert_eq!(move_at_wall(Vector::new(20.0, 5.0)), (24.0, 25.0));
+    }
+
+    #[test]
+    fn diagonal_moves_cant_tunnel_through_thin_solids() {
+        assert_eq!(move_at_wall(Vector::new(20.0, 1.0)), (24.0, 21.0));
+    }
+
+    #[test]
+    fn overlapping_solids_can_move_apart() {
+        let mut state = state([
+            Mover::boxed((0.5, 0.5), Vector::new(-1.0, 0.0)),
+            Mover::boxed((0.6, 0.5), Vector::new(1.0, 0.0)),
+        ]);
+        for entity_state in &mut state.entity_states {
+            entity_state.get_mut().pos = Some(Position { x: 50.0, y: 20.0 });
+        }
+
+        tick(&mut state);
+        assert_eq!(positions(&mut state), [(48.0, 20.0), (52.0, 20.0)]);
+    }
+
+    #[test]
+    fn solids_placed_inside_each_other_are_pushed_out() {
+        let state = state([
+            Mover::boxed((0.5, 0.5), Vector::default()),
+            Mover::boxed((0.5, 0.5), Vector::default()),
+        ]);
+
+        assert!(!overlapping(&state));
+    }
+
+    #[test]
+    fn effects_sent_to_spawns_arrive_once_added() {
+        let damage = Rc::new(Cell::new(0));
+        let mut state = state([Box::new(Spawner {
+            sprite: sprite(),
+            ticks: 0,
+            damage: damage.clone(),
+        }) as Box<dyn Entity>]);
+
+        tick(&mut state);
+        assert_eq!(damage.get(), 3);
+    }
+
+    #[test]
+    fn effects_sent_to_spawns_in_reused_slots_arrive_once_added() {
+        let damage = Rc::new(Cell::new(0));
+        let mut state = state([
+            Box::new(Doomed(sprite())) as Box<dyn Entity>,
+            Box::new(Spawner {
+                sprite: sprite(),
+                ticks: 1,
+                damage: damage.clone(),
+            }),
+        ]);
+
+        // the doomed entity's slot is freed, then reused by the spawn
+        tick(&mut state);
+        tick(&mut state);
+        assert_eq!(damage.get(), 3);
+        assert_eq!(state.entity_states.len(), 2);
     }
 }
@@ -0,0 +1,334 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use crate::entity::Vector;
+
+/// Column and row of a cell in a `NavGrid`, from the bottom-left.
+pub type Cell = (i32, i32);
+
+/// Each cell's neighbours, checked in this order so ties always go the same way.
+const NEIGHBOURS: [Cell; 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Grid of square cells over the world, each either walkable or blocked, for finding
+/// a way around solids. Get one with `World::nav_grid`, or build one by hand, e.g.
+/// from a tile map.
+///
+/// Sizes and points are in pixels, the same units as world queries and `Vector`.
+/// A cell is blocked if any solid overlaps it, so use cells at least as big as the
+/// entities finding their way.
+#[derive(Clone, Debug)]
+pub struct NavGrid {
+    cell_size: f32,
+    columns: i32,
+    rows: i32,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// An empty grid covering `width` by `height` pixels from the bottom-left.
+    pub fn new(width: f32, height: f32, cell_size: f32) -> Self {
+        let cell_size = cell_size.max(1.0);
+        let columns = (width / cell_size).ceil().max(1.0) as i32;
+        let rows = (height / cell_size).ceil().max(1.0) as i32;
+
+        Self {
+            cell_size,
+            columns,
+            rows,
+            blocked: vec![false; (columns * rows) as usize],
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Number of columns and rows.
+    pub fn size(&self) -> (i32, i32) {
+        (self.columns, self.rows)
+    }
+
+    fn index(&self, (column, row): Cell) -> Option<usize> {
+        ((0..self.columns).contains(&column) && (0..self.rows).contains(&row))
+            .then(|| (row * self.columns + column) as usize)
+    }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.index(cell).is_some()
+    }
+
+    /// Whether the cell can't be walked through. Cells off the grid are blocked.
+    pub fn is_blocked(&self, cell: Cell) -> bool {
+        match self.index(cell) {
+            Some(index) => self.blocked[index],
+            None => true,
+        }
+    }
+
+    pub fn set_blocked(&mut self, cell: Cell, blocked: bool) {
+        if let Some(index) = self.index(cell) {
+            self.blocked[index] = blocked;
+        }
+    }
+
+    /// Block every cell overlapping the rectangle, with its bottom-left corner at `x`/`y`.
+    pub fn block_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let (left, bottom) = self.cell_at((x, y));
+        let (right, top) = self.cell_at((x + width, y + height));
+
+        for column in left..=right {
+            for row in bottom..=top {
+                self.set_blocked((column, row), true);
+            }
+        }
+    }
+
+    /// The cell a point is in.
+    pub fn cell_at(&self, (x, y): (f32, f32)) -> Cell {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Middle of the cell, in pixels.
+    pub fn center_of(&self, (column, row): Cell) -> (f32, f32) {
+        (
+            (column as f32 + 0.5) * self.cell_size,
+            (row as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    /// Walkable cells next to `cell`. Paths don't cut corners, so only four.
+    fn neighbours(&self, (column, row): Cell) -> impl Iterator<Item = Cell> + '_ {
+        NEIGHBOURS
+            .into_iter()
+            .map(move |(x, y)| (column + x, row + y))
+            .filter(|&cell| !self.is_blocked(cell))
+    }
+
+    /// Shortest path between two cells with A*, including both ends.
+    /// `None` if there's no way through. The start may be blocked, e.g. by the entity
+    /// looking for the path, but the goal can't be.
+    pub fn find_path(&self, from: Cell, to: Cell) -> Option<Vec<Cell>> {
+        let (start, goal) = (self.index(from)?, self.index(to)?);
+        if self.blocked[goal] {
+            return None;
+        }
+
+        let distance = |(column, row): Cell| (column - to.0).abs() + (row - to.1).abs();
+        let mut came_from = vec![None; self.blocked.len()];
+        let mut costs = vec![i32::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        costs[start] = 0;
+        open.push(Reverse((distance(from), 0, from)));
+
+        while let Some(Reverse((_, cost, cell))) = open.pop() {
+            if cell == to {
+                let mut path = vec![to];
+                let mut cell = to;
+                while let Some(previous) = came_from[self.index(cell)?] {
+                    path.push(previous);
+                    cell = previous;
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            // already reached more cheaply
+            if cost > costs[self.index(cell)?] {
+                continue;
+            }
+
+            for next in self.neighbours(cell) {
+                let index = self.index(next)?;
+                if cost + 1 < costs[index] {
+                    costs[index] = cost + 1;
+                    came_from[index] = Some(cell);
+                    open.push(Reverse((cost + 1 + distance(next), cost + 1, next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// How many steps every cell is from `to`, spreading out with a breadth-first search.
+    pub fn distances(&self, to: Cell) -> DistanceMap {
+        let mut distances = vec![None; self.blocked.len()];
+        let mut queue = VecDeque::new();
+        if let Some(index) = self.index(to).filter(|&index| !self.blocked[index]) {
+            distances[index] = Some(0);
+            queue.push_back(to);
+        }
+
+        while let Some(cell) = queue.pop_front() {
+            let distance = self.index(cell).and_then(|index| distances[index]);
+            for next in self.neighbours(cell) {
+                let Some(index) = self.index(next) else {
+                    continue;
+                };
+                if distances[index].is_none() {
+                    distances[index] = distance.map(|distance| distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        DistanceMap {
+            grid: self.clone(),
+            distances,
+        }
+    }
+
+    /// Which way to go from every cell to reach `to`. Cheaper than a path each when
+    /// lots of entities head for the same place.
+    pub fn flow_field(&self, to: Cell) -> FlowField {
+        FlowField(self.distances(to))
+    }
+
+    /// Per-tick movement along a path, from the point `from` through the middle of
+    /// each cell after the first, moving at most `speed` pixels a tick.
+    ///
+    /// Steps are in pixels like any other `Vector`, so the engine stretches
+    /// horizontal ones to match the canvas's wider cells when moving by them.
+    pub fn steps(&self, from: (f32, f32), path: &[Cell], speed: f32) -> Vec<Vector> {
+        let mut steps = vec![];
+        if speed <= 0.0 {
+            return steps;
+        }
+
+        let mut position = Vector::new(from.0, from.1);
+        for &cell in path.iter().skip(1) {
+            let (x, y) = self.center_of(cell);
+            let offset = Vector::new(x, y) - position;
+            let ticks = (offset.length() / speed).ceil() as u32;
+            for _ in 0..ticks {
+                steps.push(offset * (1.0 / ticks as f32));
+            }
+            position = Vector::new(x, y);
+        }
+
+        steps
+    }
+}
+
+/// How many steps each cell of a `NavGrid` is from a goal.
+#[derive(Clone, Debug)]
+pub struct DistanceMap {
+    grid: NavGrid,
+    distances: Vec<Option<u32>>,
+}
+
+impl DistanceMap {
+    /// `None` if the goal can't be reached from the cell.
+    pub fn get(&self, cell: Cell) -> Option<u32> {
+        self.grid
+            .index(cell)
+            .and_then(|index| self.distances[index])
+    }
+}
+
+/// Which way to go from each cell of a `NavGrid` to reach a goal.
+#[derive(Clone, Debug)]
+pub struct FlowField(DistanceMap);
+
+impl FlowField {
+    /// The neighbouring cell one step closer to the goal.
+    /// `None` at the goal itself, and where the goal can't be reached.
+    pub fn next(&self, cell: Cell) -> Option<Cell> {
+        let distance = self.0.get(cell)?;
+        self.0
+            .grid
+            .neighbours(cell)
+            .find(|&next| self.0.get(next).is_some_and(|next| next < distance))
+    }
+
+    /// Unit vector from the cell towards the next one, or zero at the goal.
+    /// `None` where the goal can't be reached.
+    pub fn direction(&self, cell: Cell) -> Option<Vector> {
+        self.0.get(cell)?;
+        Some(match self.next(cell) {
+            Some((column, row)) => Vector::new((column - cell.0) as f32, (row - cell.1) as f32),
+            None => Vector::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 5x5 grid with a wall down the middle column, open only at the top row.
+    fn walled() -> NavGrid {
+        let mut grid = NavGrid::new(50.0, 50.0, 10.0);
+        for row in 0..4 {
+            grid.set_blocked((2, row), true);
+        }
+        grid
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let path = walled().find_path((0, 0), (4, 0)).unwrap();
+
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(4, 0)));
+        assert!(path.contains(&(2, 4)));
+        // up four, across four, down four
+        assert_eq!(path.len(), 13);
+        assert!(path
+            .windows(2)
+            .all(|pair| (pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs() == 1));
+    }
+
+    #[test]
+    fn no_path_to_blocked_or_walled_off_cells() {
+        let mut grid = walled();
+        assert_eq!(grid.find_path((0, 0), (2, 0)), None);
+
+        grid.set_blocked((2, 4), true);
+        assert_eq!(grid.find_path((0, 0), (4, 0)), None);
+    }
+
+    #[test]
+    fn distances_count_steps_around_walls() {
+        let distances = walled().distances((0, 0));
+
+        assert_eq!(distances.get((0, 0)), Some(0));
+        assert_eq!(distances.get((1, 3)), Some(4));
+        assert_eq!(distances.get((3, 0)), Some(11));
+        assert_eq!(distances.get((2, 0)), None);
+        assert_eq!(distances.get((5, 0)), None);
+    }
+
+    #[test]
+    fn flow_fields_point_along_the_way_around() {
+        let field = walled().flow_field((4, 0));
+
+        assert_eq!(field.next((3, 0)), Some((4, 0)));
+        assert_eq!(field.direction((1, 0)), Some(Vector::new(0.0, 1.0)));
+        assert_eq!(field.direction((2, 4)), Some(Vector::new(1.0, 0.0)));
+        assert_eq!(field.direction((4, 0)), Some(Vector::default()));
+        assert_eq!(field.direction((2, 0)), None);
+    }
+
+    #[test]
+    fn steps_reach_each_cell_at_most_speed_apart() {
+        let grid = NavGrid::new(50.0, 50.0, 10.0);
+        let steps = grid.steps((5.0, 5.0), &[(0, 0), (1, 0), (1, 1)], 4.0);
+
+        // 10 pixels each way, in three steps of at most 4
+        assert_eq!(steps.len(), 6);
+        assert!(steps.iter().all(|step| step.length() <= 4.0 + f32::EPSILON));
+        let total = steps
+            .iter()
+            .fold(Vector::default(), |total, step| total + *step);
+        assert!((total.x - 10.0).abs() < 1e-4 && (total.y - 10.0).abs() < 1e-4);
+
+        assert!(grid.steps((5.0, 5.0), &[(0, 0), (1, 0)], 0.0).is_empty());
+    }
+}
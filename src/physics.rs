@@ -0,0 +1,134 @@
+use crate::entity::{Edge, Vector};
+
+/// Motion the engine integrates for an entity every tick, after its `update`.
+///
+/// Velocities and accelerations are in pixels per tick, with `y` growing upwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    pub velocity: Vector,
+    pub acceleration: Vector,
+    /// Pull downwards, added to the velocity every tick.
+    pub gravity: f32,
+    /// Fraction of the velocity lost every tick, from 0 (none) to 1 (all).
+    pub drag: f32,
+    pub max_speed: Option<f32>,
+    /// Fraction of the speed kept when bouncing off a wall, from 0 (stops dead) to 1.
+    pub restitution: f32,
+    grounded: bool,
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Self {
+            velocity: Vector::default(),
+            acceleration: Vector::default(),
+            gravity: 0.0,
+            drag: 0.0,
+            max_speed: None,
+            restitution: 0.0,
+            grounded: false,
+        }
+    }
+}
+
+impl Body {
+    pub fn new(velocity: Vector) -> Self {
+        Self {
+            velocity,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_gravity(self, gravity: f32) -> Self {
+        Self { gravity, ..self }
+    }
+
+    pub fn with_drag(self, drag: f32) -> Self {
+        Self { drag, ..self }
+    }
+
+    pub fn with_max_speed(self, max_speed: f32) -> Self {
+        Self {
+            max_speed: Some(max_speed),
+            ..self
+        }
+    }
+
+    pub fn with_restitution(self, restitution: f32) -> Self {
+        Self {
+            restitution,
+            ..self
+        }
+    }
+
+    /// Push the body, changing its velocity straight away.
+    pub fn impulse(&mut self, impulse: Vector) {
+        self.velocity = self.velocity + impulse;
+    }
+
+    /// Whether the body was resting on something below it at the end of the last tick.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Advance the velocity by a tick, returning how far to move.
+    pub(crate) fn integrate(&mut self) -> Vector {
+        self.velocity = self.velocity + self.acceleration - Vector::new(0.0, self.gravity);
+        self.velocity = self.velocity * (1.0 - self.drag.clamp(0.0, 1.0));
+
+        if let Some(max_speed) = self.max_speed {
+            if self.velocity.length() > max_speed {
+                self.velocity = self.velocity.normalized() * max_speed;
+            }
+        }
+
+        self.velocity
+    }
+
+    /// Bounce off whatever stopped the body from moving the whole `step`.
+    pub(crate) fn resolve(&mut self, step: Vector, moved: Vector, grounded: bool) {
+        if moved.x != step.x {
+            self.velocity.x *= -self.restitution;
+        }
+        if moved.y != step.y {
+            self.velocity.y *= -self.restitution;
+        }
+
+        self.grounded = grounded;
+    }
+
+    /// Head away from an edge the body hit while moving by `step`, keeping the
+    /// restitution's share of the speed.
+    pub(crate) fn bounce(&mut self, edge: Edge, step: Vector) {
+        let (x, y) = (step.x.abs(), step.y.abs());
+        match edge {
+            Edge::Left => self.velocity.x = x * self.restitution,
+            Edge::Right => self.velocity.x = -x * self.restitution,
+            Edge::Bottom => self.velocity.y = y * self.restitution,
+            Edge::Top => self.velocity.y = -y * self.restitution,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounces_keep_the_restitutions_share_of_the_speed() {
+        let mut body = Body::new(Vector::new(-4.0, 2.0)).with_restitution(0.5);
+        let step = body.velocity;
+        body.bounce(Edge::Left, step);
+
+        assert_eq!(body.velocity, Vector::new(2.0, 2.0));
+    }
+
+    #[test]
+    fn only_blocked_axes_bounce() {
+        let mut body = Body::new(Vector::new(4.0, 2.0)).with_restitution(0.5);
+        let step = body.velocity;
+        body.resolve(step, Vector::new(1.0, 2.0), false);
+
+        assert_eq!(body.velocity, Vector::new(-2.0, 2.0));
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    ops::Range,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Seeded random number generator owned by the engine, so a run can be played
+/// again exactly by starting from the same seed with `Engine::set_seed`.
+/// Entities get it with `World::rng`, and scenes in `Scene::on_timer`.
+///
+/// Not suitable for anything that needs to be unpredictable, like passwords.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Seeded from the current time, for when runs don't need to be repeatable.
+    pub(crate) fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64);
+
+        Self::new(nanos)
+    }
+
+    /// The seed it started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        // splitmix64
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Between 0 and 1, including 0 but not 1.
+    pub fn next_f32(&mut self) -> f32 {
+        // the top 24 bits fit exactly in an f32's mantissa
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Somewhere in `range`, or its start if it's empty.
+    pub fn range(&mut self, range: Range<i32>) -> i32 {
+        if range.is_empty() {
+            return range.start;
+        }
+
+        let len = (range.end as i64 - range.start as i64) as u64;
+        (range.start as i64 + (self.next_u64() % len) as i64) as i32
+    }
+
+    /// Somewhere in `range`, or its start if it's empty.
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        if range.is_empty() {
+            return range.start;
+        }
+
+        range.start + self.next_f32() * (range.end - range.start)
+    }
+
+    /// True with the given probability, from 0 to 1.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// One of the items, or `None` if there aren't any.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        let index = self.range(0..items.len().min(i32::MAX as usize) as i32);
+        items.get(index as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        let numbers: Vec<_> = (0..8).map(|_| a.next_u64()).collect();
+
+        assert_eq!(numbers, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(numbers[0], Rng::new(8).next_u64());
+        assert_eq!(a.seed(), 7);
+    }
+
+    #[test]
+    fn sequence_is_splitmix64() {
+        // reference values for splitmix64 seeded with 0
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+    }
+
+    #[test]
+    fn numbers_stay_in_range() {
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            assert!((-3..4).contains(&rng.range(-3..4)));
+            assert!((0.0..1.0).contains(&rng.next_f32()));
+            assert!((2.0..3.0).contains(&rng.range_f32(2.0..3.0)));
+        }
+        assert!((i32::MIN..i32::MAX).contains(&rng.range(i32::MIN..i32::MAX)));
+    }
+
+    #[test]
+    fn empty_ranges_give_their_start() {
+        let mut rng = Rng::new(1);
+
+        assert_eq!(rng.range(5..5), 5);
+        #[allow(clippy::reversed_empty_ranges)]
+        let backwards = rng.range(5..2);
+        assert_eq!(backwards, 5);
+        assert_eq!(rng.range_f32(1.5..1.5), 1.5);
+        assert_eq!(rng.pick::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn chance_extremes() {
+        let mut rng = Rng::new(1);
+
+        assert!((0..100).all(|_| rng.chance(1.0)));
+        assert!((0..100).all(|_| !rng.chance(0.0)));
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{entity::Input, GameError, FPS_BOUNDS};
+
+/// First line of every recording, so other files are turned away.
+const HEADER: &str = "replay v1";
+
+// Recordings are plain text: the header, then what the run depends on besides input,
+// then the input each tick, one per line. They're only the same run again in a
+// canvas of the same size, at the same FPS, with the same seed.
+
+/// Writes each tick's input to a file as the game runs, with `Engine::record_to`.
+#[derive(Debug)]
+pub(crate) struct Recorder(BufWriter<File>);
+
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> Result<Self, GameError> {
+        Ok(Self(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Write down what the run depends on, once it's known at startup.
+    pub(crate) fn start(&mut self, seed: u64, fps: u32, size: (u16, u16)) -> Result<(), GameError> {
+        writeln!(self.0, "{HEADER}")?;
+        writeln!(self.0, "seed {seed}")?;
+        writeln!(self.0, "fps {fps}")?;
+        writeln!(self.0, "size {} {}", size.0, size.1)?;
+        Ok(())
+    }
+
+    pub(crate) fn record(&mut self, input: Input) -> Result<(), GameError> {
+        writeln!(self.0, "{input}")?;
+        Ok(())
+    }
+}
+
+/// A recording being fed back into the game, with `Engine::replay_from`.
+pub(crate) struct Replay {
+    pub(crate) seed: u64,
+    pub(crate) fps: u32,
+    pub(crate) size: (u16, u16),
+    inputs: VecDeque<Input>,
+}
+
+impl Replay {
+    pub(crate) fn load(path: &Path) -> Result<Self, GameError> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid("not a recording"));
+        }
+
+        let mut setting = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .and_then(|value| value.strip_prefix(' '))
+                .ok_or_else(|| invalid(&format!("missing {name}")))
+        };
+        let seed = setting("seed")?.parse().map_err(|_| invalid("bad seed"))?;
+        let fps = setting("fps")?
+            .parse()
+            .ok()
+            .filter(|fps| FPS_BOUNDS.contains(fps))
+            .ok_or_else(|| invalid("bad fps"))?;
+        let size = setting("size")?
+            .split_once(' ')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .filter(|&(width, height): &(u16, u16)| width > 0 && height > 0)
+            .ok_or_else(|| invalid("bad size"))?;
+
+        let inputs = lines
+            .map(|line| {
+                line.parse()
+                    .map_err(|_| invalid(&format!("bad input {line:?}")))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            seed,
+            fps,
+            size,
+            inputs,
+        })
+    }
+
+    /// Input for the next tick, or `None` once the recording runs out.
+    pub(crate) fn next(&mut self) -> Option<Input> {
+        self.inputs.pop_front()
+    }
+}
+
+impl Debug for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("seed", &self.seed)
+            .field("fps", &self.fps)
+            .field("size", &self.size)
+            .field("ticks_left", &self.inputs.len())
+            .finish()
+    }
+}
+
+fn invalid(reason: &str) -> GameError {
+    GameError::InvalidReplay(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf};
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("game-{}-{name}", std::process::id()))
+    }
+
+    fn load(name: &str, contents: &str) -> Result<Replay, GameError> {
+        let path = temp_path(name);
+        fs::write(&path, contents).unwrap();
+        let replay = Replay::load(&path);
+        fs::remove_file(path).unwrap();
+        replay
+    }
+
+    #[test]
+    fn recordings_load_back() {
+        let path = temp_path("round-trip.rec");
+        {
+            let mut recorder = Recorder::create(&path).unwrap();
+            recorder.start(42, 10, (80, 24)).unwrap();
+            for input in Input::ALL {
+                recorder.record(input).unwrap();
+            }
+        }
+
+        let mut replay = Replay::load(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!((replay.seed, replay.fps, replay.size), (42, 10, (80, 24)));
+        for input in Input::ALL {
+            assert_eq!(replay.next(), Some(input));
+        }
+        assert_eq!(replay.next(), None);
+    }
+
+    #[test]
+    fn fps_out_of_bounds_is_invalid() {
+        let replay = load("zero-fps.rec", "replay v1\nseed 1\nfps 0\nsize 80 24\n");
+        assert!(matches!(replay, Err(GameError::InvalidReplay(_))));
+    }
+
+    #[test]
+    fn empty_size_is_invalid() {
+        let replay = load("empty-size.rec", "replay v1\nseed 1\nfps 10\nsize 0 24\n");
+        assert!(matches!(replay, Err(GameError::InvalidReplay(_))));
+    }
+
+    #[test]
+    fn unknown_inputs_are_invalid() {
+        let replay = load(
+            "bad-input.rec",
+            "replay v1\nseed 1\nfps 10\nsize 80 24\nJump\n",
+        );
+        assert!(matches!(replay, Err(GameError::InvalidReplay(_))));
+    }
+}
@@ -0,0 +1,214 @@
+use ratatui::{buffer::Buffer, prelude::*, widgets::Widget};
+use std::fmt::Debug;
+
+use crate::{
+    draw::Draw,
+    entity::{Entity, Input},
+    event::Event,
+    random::Rng,
+    timer::Timer,
+    GameError, State,
+};
+
+/// A screen of the game with its own entities, e.g. a title menu, gameplay or game over.
+/// Scenes are kept on a stack, and only the top one is updated.
+pub trait Scene: Debug {
+    /// Entities the scene starts with, called once when the scene is pushed.
+    fn starting_entities(&mut self) -> Vec<Box<dyn Entity>> {
+        vec![]
+    }
+
+    /// Timers the scene starts with, called once when the scene is pushed.
+    /// They stop when the scene is popped.
+    fn starting_timers(&mut self) -> Vec<Timer> {
+        vec![]
+    }
+
+    /// Respond to one of the scene's timers going off, returning entities to add,
+    /// e.g. for a wave of enemies placed with the game's random numbers.
+    fn on_timer(&mut self, _name: &'static str, _rng: &mut Rng) -> Vec<Box<dyn Entity>> {
+        vec![]
+    }
+
+    /// Update the scene for this game tick, before its entities are updated.
+    fn update(&mut self, _input: Input) -> SceneChange {
+        SceneChange::None
+    }
+
+    /// Respond to an event published by one of the scene's entities.
+    fn on_event(&mut self, _event: &Event) {}
+
+    /// Draw on top of the scene's entities.
+    fn draw(&self, _draw: &mut Draw) {}
+
+    /// Whether the scenes below stay visible (but frozen) underneath, e.g. for a pause menu.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// How the screen changes when this scene is pushed, or when it's popped off.
+    fn transition(&self) -> Transition {
+        Transition::Cut
+    }
+}
+
+/// Change to the scene stack requested by the top scene.
+#[derive(Debug, Default)]
+pub enum SceneChange {
+    #[default]
+    None,
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+    Quit,
+}
+
+/// Visual effect played while the scene stack changes.
+/// Updates are frozen while it plays, and the stack changes halfway through.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Transition {
+    #[default]
+    Cut,
+    /// Fade to black and back over the given number of ticks.
+    Fade(u32),
+    /// Sweep black across the screen and back over the given number of ticks.
+    Wipe(u32),
+}
+
+impl Transition {
+    pub(crate) fn ticks(self) -> u32 {
+        match self {
+            Transition::Cut => 0,
+            Transition::Fade(ticks) | Transition::Wipe(ticks) => ticks,
+        }
+    }
+}
+
+/// The scene at the bottom of the stack holding `Engine::starting_entities`, or an
+/// empty one when the game has no scenes at all.
+#[derive(Debug)]
+pub(crate) struct Root;
+
+impl Scene for Root {}
+
+#[derive(Debug)]
+pub(crate) struct SceneState {
+    pub(crate) scene: Box<dyn Scene>,
+    pub(crate) state: State,
+}
+
+impl SceneState {
+    /// Build a scene's state, adding its starting entities.
+    pub(crate) fn new(mut scene: Box<dyn Scene>) -> Self {
+        let mut state = State::new();
+        for entity in scene.starting_entities() {
+            state.add_entity(entity);
+        }
+        for timer in scene.starting_timers() {
+            state.timers.start(None, timer);
+        }
+
+        Self { scene, state }
+    }
+
+    /// Place the scene's entities within `bounds`.
+    pub(crate) fn start(&mut self, bounds: Rect) -> Result<(), GameError> {
+        self.state.set_bounds(bounds);
+        self.state.set_starting_positions()
+    }
+}
+
+/// A scene change in progress, with its transition.
+#[derive(Debug)]
+pub(crate) struct Playing {
+    transition: Transition,
+    tick: u32,
+    change: Option<SceneChange>,
+}
+
+impl Playing {
+    pub(crate) fn new(transition: Transition, change: SceneChange) -> Self {
+        Self {
+            transition,
+            tick: 0,
+            change: Some(change),
+        }
+    }
+
+    /// Advance the transition by a tick. Returns the scene change once it's time to apply it.
+    pub(crate) fn advance(&mut self) -> Option<SceneChange> {
+        self.tick += 1;
+        if self.tick >= self.transition.ticks() / 2 {
+            self.change.take()
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.change.is_none() && self.tick >= self.transition.ticks()
+    }
+
+    /// How far the screen is covered, from 0 (clear) to 1 (fully covered).
+    fn coverage(&self) -> f32 {
+        let half = (self.transition.ticks() / 2).max(1) as f32;
+        let tick = self.tick as f32;
+
+        if self.change.is_some() {
+            (tick / half).min(1.0)
+        } else {
+            ((self.transition.ticks() as f32 - tick) / half).clamp(0.0, 1.0)
+        }
+    }
+
+    /// Cover the already rendered frame according to the transition's progress.
+    pub(crate) fn cover(&self) -> Cover {
+        Cover {
+            transition: self.transition,
+            coverage: self.coverage(),
+        }
+    }
+}
+
+/// Widget that darkens or hides whatever was rendered beneath it.
+pub(crate) struct Cover {
+    transition: Transition,
+    coverage: f32,
+}
+
+impl Widget for Cover {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        match self.transition {
+            Transition::Cut => {}
+
+            Transition::Fade(_) => {
+                let dim = |color| match color {
+                    Color::Rgb(r, g, b) => {
+                        let scale = |c: u8| (c as f32 * (1.0 - self.coverage)) as u8;
+                        Color::Rgb(scale(r), scale(g), scale(b))
+                    }
+                    color => color,
+                };
+
+                for y in area.top()..area.bottom() {
+                    for x in area.left()..area.right() {
+                        let cell = buf.get_mut(x, y);
+                        cell.fg = dim(cell.fg);
+                        cell.bg = dim(cell.bg);
+                    }
+                }
+            }
+
+            Transition::Wipe(_) => {
+                let columns = (area.width as f32 * self.coverage) as u16;
+                for y in area.top()..area.bottom() {
+                    for x in area.left()..area.left() + columns {
+                        buf.get_mut(x, y)
+                            .set_symbol(" ")
+                            .set_bg(Color::Rgb(0, 0, 0));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+/// Cell size used by the engine, in canvas cells. About the size of a typical sprite.
+pub(crate) const DEFAULT_CELL_SIZE: i32 = 16;
+
+/// Box in canvas coordinates, with inclusive `left`/`bottom`/`right`/`top` edges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub left: i32,
+    pub bottom: i32,
+    pub right: i32,
+    pub top: i32,
+}
+
+impl Bounds {
+    pub fn new(left: i32, bottom: i32, right: i32, top: i32) -> Self {
+        // sprites narrower than their collision margin end up inverted
+        Self {
+            left: left.min(right),
+            bottom: bottom.min(top),
+            right: left.max(right),
+            top: bottom.max(top),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.left <= other.right
+            && self.right >= other.left
+            && self.bottom <= other.top
+            && self.top >= other.bottom
+    }
+}
+
+/// Broad phase for collisions and world queries: a uniform grid bucketing items by
+/// the cells their bounds touch, so only nearby items need to be checked.
+#[derive(Debug)]
+pub struct SpatialGrid {
+    cell_size: i32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: i32) -> Self {
+        Self {
+            cell_size: cell_size.max(1),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // keep the buckets around so rebuilding every tick doesn't reallocate
+        for items in self.cells.values_mut() {
+            items.clear();
+        }
+    }
+
+    fn cells(&self, bounds: Bounds) -> impl Iterator<Item = (i32, i32)> {
+        let (left, right) = (
+            bounds.left.div_euclid(self.cell_size),
+            bounds.right.div_euclid(self.cell_size),
+        );
+        let (bottom, top) = (
+            bounds.bottom.div_euclid(self.cell_size),
+            bounds.top.div_euclid(self.cell_size),
+        );
+
+        (left..=right).flat_map(move |x| (bottom..=top).map(move |y| (x, y)))
+    }
+
+    pub fn insert(&mut self, item: usize, bounds: Bounds) {
+        for cell in self.cells(bounds) {
+            self.cells.entry(cell).or_default().push(item);
+        }
+    }
+
+    pub fn remove(&mut self, item: usize, bounds: Bounds) {
+        for cell in self.cells(bounds) {
+            if let Some(items) = self.cells.get_mut(&cell) {
+                items.retain(|other| *other != item);
+            }
+        }
+    }
+
+    /// Move an item that was inserted with `old` bounds.
+    pub fn update(&mut self, item: usize, old: Bounds, new: Bounds) {
+        if self.cells(old).eq(self.cells(new)) {
+            return;
+        }
+
+        self.remove(item, old);
+        self.insert(item, new);
+    }
+
+    /// Items sharing a cell with `bounds`, in ascending order and without duplicates.
+    /// These might overlap `bounds`; anything else definitely doesn't.
+    pub fn query(&self, bounds: Bounds) -> Vec<usize> {
+        let mut items: Vec<_> = self
+            .cells(bounds)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+
+        items.sort_unstable();
+        items.dedup();
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverted_bounds_are_straightened() {
+        assert_eq!(Bounds::new(5, 6, 1, 2), Bounds::new(1, 2, 5, 6));
+    }
+
+    #[test]
+    fn items_across_cell_borders_are_found_once() {
+        let mut grid = SpatialGrid::new(10);
+        grid.insert(0, Bounds::new(5, 5, 15, 15));
+        grid.insert(1, Bounds::new(-8, -8, -2, -2));
+        grid.insert(2, Bounds::new(30, 30, 32, 32));
+
+        assert_eq!(grid.query(Bounds::new(0, 0, 20, 20)), [0]);
+        assert_eq!(grid.query(Bounds::new(-5, -5, 12, 1)), [0, 1]);
+        assert_eq!(grid.query(Bounds::new(50, 50, 60, 60)), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn updated_items_leave_their_old_cells() {
+        let mut grid = SpatialGrid::new(10);
+        let (old, new) = (Bounds::new(1, 1, 3, 3), Bounds::new(9, 1, 11, 3));
+        grid.insert(0, old);
+
+        grid.update(0, old, new);
+        assert_eq!(grid.query(Bounds::new(12, 0, 14, 2)), [0]);
+
+        let newer = Bounds::new(21, 1, 23, 3);
+        grid.update(0, new, newer);
+        assert_eq!(grid.query(Bounds::new(0, 0, 19, 9)), Vec::<usize>::new());
+        assert_eq!(grid.query(newer), [0]);
+    }
+
+    #[test]
+    fn cleared_grids_are_empty() {
+        let mut grid = SpatialGrid::new(10);
+        grid.insert(0, Bounds::new(0, 0, 5, 5));
+        grid.clear();
+
+        assert!(grid.query(Bounds::new(0, 0, 5, 5)).is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+/// Timed condition the engine tracks for an entity, applied with `Effect::Status`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Status {
+    pub kind: StatusKind,
+    /// How many more ticks it lasts.
+    pub ticks: u32,
+    pub stacking: Stacking,
+}
+
+impl Status {
+    pub fn new(kind: StatusKind, ticks: u32) -> Self {
+        Self {
+            kind,
+            ticks,
+            stacking: Stacking::default(),
+        }
+    }
+
+    pub fn with_stacking(self, stacking: Stacking) -> Self {
+        Self { stacking, ..self }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StatusKind {
+    /// Move at the given fraction of the usual speed.
+    Slow(f32),
+    /// Skip `Entity::update` entirely. Bodies still move.
+    Stun,
+    /// Ignore `Effect::Damage` sent through the world.
+    Invulnerable,
+    /// Tracked by the engine, but only the game knows what it does.
+    Custom(&'static str),
+}
+
+impl StatusKind {
+    /// Whether two statuses count as the same for stacking, e.g. slows of any speed.
+    fn is_like(self, other: Self) -> bool {
+        match (self, other) {
+            (StatusKind::Custom(name), StatusKind::Custom(other_name)) => name == other_name,
+            _ => std::mem::discriminant(&self) == std::mem::discriminant(&other),
+        }
+    }
+}
+
+/// What happens when a status is applied to an entity that already has one like it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Stacking {
+    /// Replace the old one, keeping whichever lasts longer.
+    #[default]
+    Refresh,
+    /// Add the new duration to the old one.
+    Extend,
+    /// Keep the old one and ignore the new one.
+    Keep,
+    /// Track both separately, e.g. so slows multiply.
+    Stack,
+}
+
+/// Statuses an entity currently has.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Statuses(Vec<Status>);
+
+impl Statuses {
+    pub(crate) fn apply(&mut self, status: Status) {
+        if status.ticks == 0 {
+            return;
+        }
+
+        let existing = self
+            .0
+            .iter_mut()
+            .find(|existing| existing.kind.is_like(status.kind));
+
+        match (existing, status.stacking) {
+            (None, _) | (Some(_), Stacking::Stack) => self.0.push(status),
+            (Some(existing), Stacking::Refresh) => {
+                existing.kind = status.kind;
+                existing.ticks = existing.ticks.max(status.ticks);
+            }
+            (Some(existing), Stacking::Extend) => existing.ticks += status.ticks,
+            (Some(_), Stacking::Keep) => {}
+        }
+    }
+
+    /// Count down a tick, returning the statuses that just ran out.
+    pub(crate) fn tick(&mut self) -> Vec<StatusKind> {
+        let mut expired = vec![];
+        self.0.retain_mut(|status| {
+            status.ticks -= 1;
+            if status.ticks == 0 {
+                expired.push(status.kind);
+            }
+            status.ticks > 0
+        });
+
+        expired
+    }
+
+    /// Whether there's a status like `kind`, e.g. a slow of any speed.
+    pub(crate) fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|status| status.kind.is_like(kind))
+    }
+
+    /// Each status with the ticks it has left, for the debug overlay.
+    pub(crate) fn describe(&self) -> Option<String> {
+        let descriptions: Vec<_> = self
+            .0
+            .iter()
+            .map(|status| format!("{:?} {}", status.kind, status.ticks))
+            .collect();
+
+        (!descriptions.is_empty()).then(|| descriptions.join(", "))
+    }
+
+    pub(crate) fn is_stunned(&self) -> bool {
+        self.0.iter().any(|status| status.kind == StatusKind::Stun)
+    }
+
+    pub(crate) fn is_invulnerable(&self) -> bool {
+        self.0
+            .iter()
+            .any(|status| status.kind == StatusKind::Invulnerable)
+    }
+
+    /// Fraction of the usual speed to move at, after every slow.
+    pub(crate) fn speed(&self) -> f32 {
+        self.0
+            .iter()
+            .filter_map(|status| match status.kind {
+                StatusKind::Slow(speed) => Some(speed),
+                _ => None,
+            })
+            .product()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(applied: &[Status]) -> Statuses {
+        let mut statuses = Statuses::default();
+        for status in applied {
+            statuses.apply(*status);
+        }
+        statuses
+    }
+
+    fn slow(speed: f32, ticks: u32, stacking: Stacking) -> Status {
+        Status::new(StatusKind::Slow(speed), ticks).with_stacking(stacking)
+    }
+
+    #[test]
+    fn refreshing_keeps_the_new_kind_and_the_longer_duration() {
+        let statuses = statuses(&[
+            slow(0.5, 5, Stacking::Refresh),
+            slow(0.25, 2, Stacking::Refresh),
+        ]);
+
+        assert_eq!(statuses.0, [slow(0.25, 5, Stacking::Refresh)]);
+    }
+
+    #[test]
+    fn extending_adds_up_the_durations() {
+        let statuses = statuses(&[
+            slow(0.5, 5, Stacking::Refresh),
+            slow(0.25, 2, Stacking::Extend),
+        ]);
+
+        assert_eq!(statuses.0, [slow(0.5, 7, Stacking::Refresh)]);
+    }
+
+    #[test]
+    fn keeping_ignores_the_new_status() {
+        let statuses = statuses(&[
+            slow(0.5, 5, Stacking::Refresh),
+            slow(0.25, 9, Stacking::Keep),
+        ]);
+
+        assert_eq!(statuses.0, [slow(0.5, 5, Stacking::Refresh)]);
+    }
+
+    #[test]
+    fn stacked_slows_multiply_and_expire_separately() {
+        let mut statuses =
+            statuses(&[slow(0.5, 1, Stacking::Stack), slow(0.5, 2, Stacking::Stack)]);
+        assert_eq!(statuses.speed(), 0.25);
+
+        assert_eq!(statuses.tick(), [StatusKind::Slow(0.5)]);
+        assert_eq!(statuses.speed(), 0.5);
+        assert_eq!(statuses.tick(), [StatusKind::Slow(0.5)]);
+        assert_eq!(statuses.speed(), 1.0);
+    }
+
+    #[test]
+    fn only_like_statuses_stack_with_each_other() {
+        let statuses = statuses(&[
+            Status::new(StatusKind::Custom("burn"), 3),
+            Status::new(StatusKind::Custom("freeze"), 3),
+            Status::new(StatusKind::Stun, 3).with_stacking(Stacking::Keep),
+            Status::new(StatusKind::Custom("burn"), 3).with_stacking(Stacking::Extend),
+        ]);
+
+        assert_eq!(statuses.0.len(), 3);
+        assert_eq!(statuses.0[0].ticks, 6);
+        assert!(statuses.is_stunned());
+        assert!(!statuses.is_invulnerable());
+    }
+
+    #[test]
+    fn statuses_without_ticks_are_ignored() {
+        let statuses = statuses(&[Status::new(StatusKind::Stun, 0)]);
+
+        assert!(!statuses.is_stunned());
+    }
+}
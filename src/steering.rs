@@ -0,0 +1,261 @@
+use std::f32::consts::TAU;
+
+use crate::{
+    entity::{Entity, Vector},
+    world::{Seen, World},
+};
+
+/// Furthest ahead, in ticks, that `pursue` and `evade` guess where a target is going.
+const MAX_PREDICTION: f32 = 30.0;
+
+// Steering behaviors: each works out a step for the entity being updated from what
+// it can see in the world, ready to return in `Update::Action`. Steps are in pixels
+// and at most `speed` long, and can be added together to combine behaviors, e.g.
+// chasing the player while keeping away from other enemies.
+
+/// Head straight for a point.
+pub fn seek(world: &World, target: (f32, f32), speed: f32) -> Vector {
+    towards(world.center(), target).normalized() * speed
+}
+
+/// Head straight away from a point.
+pub fn flee(world: &World, threat: (f32, f32), speed: f32) -> Vector {
+    -seek(world, threat, speed)
+}
+
+/// Head for a point, slowing down within `slowing_radius` pixels to stop on it.
+pub fn arrive(world: &World, target: (f32, f32), speed: f32, slowing_radius: f32) -> Vector {
+    let offset = towards(world.center(), target);
+    let distance = offset.length();
+    let speed = if distance < slowing_radius {
+        speed * distance / slowing_radius
+    } else {
+        speed
+    };
+
+    // never overshoot
+    offset.normalized() * speed.min(distance)
+}
+
+/// Head for where a moving entity will be when we'd reach it.
+pub fn pursue(world: &World, target: &Seen, speed: f32) -> Vector {
+    seek(world, predict(target, speed), speed)
+}
+
+/// Head away from where a moving entity will be when it'd reach us.
+pub fn evade(world: &World, threat: &Seen, speed: f32) -> Vector {
+    flee(world, predict(threat, speed), speed)
+}
+
+/// Keep away from other entities matching `filter` within `radius` pixels, pushing
+/// harder the closer they are.
+pub fn separation<F>(world: &World, radius: f32, speed: f32, filter: F) -> Vector
+where
+    F: Fn(&dyn Entity) -> bool,
+{
+    let push =
+        world
+            .within_radius(radius, filter)
+            .into_iter()
+            .fold(Vector::default(), |push, other| {
+                let away = -Vector::new(other.offset.0, other.offset.1).normalized();
+                push + away * (1.0 - other.distance / radius)
+            });
+
+    limit(push, 1.0) * speed
+}
+
+/// Drift around aimlessly, turning a little each tick. Keep one per entity.
+#[derive(Clone, Debug)]
+pub struct Wander {
+    heading: f32,
+    max_turn: f32,
+}
+
+impl Wander {
+    /// Turn by up to `max_turn` radians a tick, using the game's random numbers.
+    pub fn new(max_turn: f32) -> Self {
+        Self {
+            heading: 0.0,
+            max_turn,
+        }
+    }
+
+    pub fn update(&mut self, world: &mut World, speed: f32) -> Vector {
+        let turn = world.rng().range_f32(-self.max_turn..self.max_turn);
+
+        self.heading = (self.heading + turn).rem_euclid(TAU);
+        Vector::new(self.heading.cos(), self.heading.sin()) * speed
+    }
+}
+
+fn towards(from: (f32, f32), to: (f32, f32)) -> Vector {
+    Vector::new(to.0 - from.0, to.1 - from.1)
+}
+
+/// Where `target` will be after the ticks it'd take to cover the distance to it at `speed`.
+fn predict(target: &Seen, speed: f32) -> (f32, f32) {
+    let ticks = if speed > 0.0 {
+        (target.distance / speed).min(MAX_PREDICTION)
+    } else {
+        0.0
+    };
+
+    (
+        target.center.0 + target.velocity.0 * ticks,
+        target.center.1 + target.velocity.1 * ticks,
+    )
+}
+
+/// `vector`, shortened to at most `max` long.
+fn limit(vector: Vector, max: f32) -> Vector {
+    if vector.length() > max {
+        vector.normalized() * max
+    } else {
+        vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        entity::{Effect, EntityId, Input, Sprite, Update},
+        random::Rng,
+        State,
+    };
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(vector: Vector, x: f32, y: f32) {
+        assert!(
+            (vector.x - x).abs() < EPSILON && (vector.y - y).abs() < EPSILON,
+            "{vector:?} isn't close to ({x}, {y})"
+        );
+    }
+
+    /// `Seen` at `offset` pixels from `center`, moving by `velocity` a tick.
+    fn seen(center: (f32, f32), offset: (f32, f32), velocity: (f32, f32)) -> Seen {
+        Seen {
+            id: EntityId::new(1, 0),
+            center: (center.0 + offset.0, center.1 + offset.1),
+            offset,
+            distance: offset.0.hypot(offset.1),
+            velocity,
+        }
+    }
+
+    #[test]
+    fn seeking_and_fleeing_move_at_speed() {
+        World::with_empty(|world| {
+            let (x, y) = world.center();
+
+            assert_close(seek(world, (x + 10.0, y), 2.0), 2.0, 0.0);
+            assert_close(flee(world, (x, y + 10.0), 2.0), 0.0, -2.0);
+        });
+    }
+
+    #[test]
+    fn arriving_slows_down_and_never_overshoots() {
+        World::with_empty(|world| {
+            let (x, y) = world.center();
+
+            // too close to slow down in time, so it stops on the target
+            assert_close(arrive(world, (x + 1.0, y), 5.0, 0.5), 1.0, 0.0);
+            assert_close(arrive(world, (x + 2.0, y), 5.0, 10.0), 1.0, 0.0);
+            assert_close(arrive(world, (x + 20.0, y), 5.0, 10.0), 5.0, 0.0);
+            assert_close(arrive(world, (x, y), 5.0, 10.0), 0.0, 0.0);
+        });
+    }
+
+    #[test]
+    fn predictions_are_capped() {
+        let target = seen((0.0, 0.0), (1000.0, 0.0), (0.0, 1.0));
+
+        assert_eq!(predict(&target, 1.0), (1000.0, MAX_PREDICTION));
+        assert_eq!(predict(&target, 0.0), (1000.0, 0.0));
+    }
+
+    #[test]
+    fn pursuing_leads_the_target_and_evading_runs_from_where_it_will_be() {
+        World::with_empty(|world| {
+            let target = seen(world.center(), (10.0, 0.0), (0.0, 1.0));
+
+            let chase = pursue(world, &target, 2.0);
+            assert!(chase.x > 0.0 && chase.y > 0.0);
+            assert!((chase.length() - 2.0).abs() < EPSILON);
+            assert_close(evade(world, &target, 2.0), -chase.x, -chase.y);
+        });
+    }
+
+    #[test]
+    fn wandering_turns_a_little_at_a_time() {
+        World::with_empty(|world| {
+            let mut wander = Wander::new(0.1);
+            let mut heading = 0.0_f32;
+            for _ in 0..20 {
+                let step = wander.update(world, 2.0);
+                let turned = step.y.atan2(step.x) - heading;
+
+                assert!((step.length() - 2.0).abs() < EPSILON);
+                assert!(turned.sin().abs() <= 0.1_f32.sin() + EPSILON);
+                heading = step.y.atan2(step.x);
+            }
+        });
+    }
+
+    /// Notes its separation from the others every tick.
+    #[derive(Debug)]
+    struct Crowded {
+        sprite: Rc<Sprite>,
+        start: (f32, f32),
+        pushes: Rc<RefCell<Vec<Vector>>>,
+    }
+
+    impl Entity for Crowded {
+        fn start_pos(&self) -> (f32, f32) {
+            self.start
+        }
+
+        fn sprite(&self) -> &Rc<Sprite> {
+            &self.sprite
+        }
+
+        fn update(&mut self, _input: Input, world: &mut World) -> Update {
+            let push = separation(world, 10.0, 2.0, |entity| entity.is::<Crowded>());
+            self.pushes.borrow_mut().push(push);
+            Update::None
+        }
+
+        fn collision(&mut self, _other: &mut Box<dyn Entity>, _other_id: EntityId) {}
+
+        fn effect(&mut self, _effect: Effect) {}
+    }
+
+    #[test]
+    fn separation_pushes_away_no_faster_than_speed() {
+        let pushes = Rc::new(RefCell::new(vec![]));
+        let mut state = State::new();
+        for start in [(0.5, 0.5), (0.53, 0.5), (0.53, 0.55), (0.53, 0.45)] {
+            state.add_entity(Box::new(Crowded {
+                sprite: Rc::new(Sprite::filled(1, 1, (255, 255, 255))),
+                start,
+                pushes: pushes.clone(),
+            }));
+        }
+        state.set_bounds(Rect::new(0, 0, 100, 50));
+        state.set_starting_positions().unwrap();
+        state
+            .update_entities(Input::None, 10, &mut Rng::new(0))
+            .unwrap();
+
+        let pushes = pushes.borrow();
+        assert!(pushes.iter().all(|push| push.length() <= 2.0 + EPSILON));
+        // crowded on one side, so pushed away from it as hard as it can be
+        assert!(pushes[0].x < 0.0);
+        assert!((pushes[0].length() - 2.0).abs() < EPSILON);
+    }
+}
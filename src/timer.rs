@@ -0,0 +1,171 @@
+use crate::entity::EntityId;
+
+/// How long a timer waits, in game ticks or seconds.
+/// Seconds are counted in ticks at the game's FPS, so they slow down with the game.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Delay {
+    Ticks(u32),
+    Seconds(f32),
+}
+
+impl Delay {
+    fn ticks(self, fps: u32) -> u32 {
+        match self {
+            Delay::Ticks(ticks) => ticks.max(1),
+            Delay::Seconds(seconds) => ((seconds * fps as f32).round() as u32).max(1),
+        }
+    }
+}
+
+/// A named countdown, started by an entity with `World::start_timer` or by a scene
+/// with `Scene::starting_timers`. The owner's `on_timer` is called when it goes off.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Timer {
+    name: &'static str,
+    delay: Delay,
+    repeat: bool,
+}
+
+impl Timer {
+    /// Go off once, after `delay`.
+    pub fn after(name: &'static str, delay: Delay) -> Self {
+        Self {
+            name,
+            delay,
+            repeat: false,
+        }
+    }
+
+    /// Go off every `interval`, until cancelled.
+    pub fn every(name: &'static str, interval: Delay) -> Self {
+        Self {
+            name,
+            delay: interval,
+            repeat: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Running {
+    // `None` for the scene's own timers
+    owner: Option<EntityId>,
+    timer: Timer,
+    elapsed: u32,
+}
+
+/// Every timer running in a scene, counted down once per tick.
+#[derive(Debug, Default)]
+pub(crate) struct Timers(Vec<Running>);
+
+impl Timers {
+    /// Start a timer, replacing any the owner already has with the same name.
+    pub(crate) fn start(&mut self, owner: Option<EntityId>, timer: Timer) {
+        self.cancel(owner, timer.name);
+        self.0.push(Running {
+            owner,
+            timer,
+            elapsed: 0,
+        });
+    }
+
+    pub(crate) fn cancel(&mut self, owner: Option<EntityId>, name: &str) {
+        self.0
+            .retain(|running| running.owner != owner || running.timer.name != name);
+    }
+
+    /// Stop every timer belonging to a destroyed entity.
+    pub(crate) fn cancel_all(&mut self, owner: EntityId) {
+        self.0.retain(|running| running.owner != Some(owner));
+    }
+
+    pub(crate) fn is_running(&self, owner: Option<EntityId>, name: &str) -> bool {
+        self.0
+            .iter()
+            .any(|running| running.owner == owner && running.timer.name == name)
+    }
+
+    /// Count down a tick, returning the timers that went off, oldest first.
+    pub(crate) fn tick(&mut self, fps: u32) -> Vec<(Option<EntityId>, &'static str)> {
+        let mut fired = vec![];
+        self.0.retain_mut(|running| {
+            running.elapsed += 1;
+            if running.elapsed < running.timer.delay.ticks(fps) {
+                return true;
+            }
+
+            fired.push((running.owner, running.timer.name));
+            running.elapsed = 0;
+            running.timer.repeat
+        });
+
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FPS: u32 = 10;
+
+    /// Ticks `timers` `ticks` times, returning the names that went off on each.
+    fn run(timers: &mut Timers, ticks: usize) -> Vec<Vec<&'static str>> {
+        (0..ticks)
+            .map(|_| timers.tick(FPS).into_iter().map(|(_, name)| name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn one_shot_timers_go_off_once() {
+        let mut timers = Timers::default();
+        timers.start(None, Timer::after("once", Delay::Ticks(2)));
+
+        assert_eq!(run(&mut timers, 4), [vec![], vec!["once"], vec![], vec![]]);
+        assert!(!timers.is_running(None, "once"));
+    }
+
+    #[test]
+    fn repeating_timers_go_off_every_interval() {
+        let mut timers = Timers::default();
+        timers.start(None, Timer::every("often", Delay::Ticks(2)));
+
+        assert_eq!(
+            run(&mut timers, 4),
+            [vec![], vec!["often"], vec![], vec!["often"]]
+        );
+        assert!(timers.is_running(None, "often"));
+    }
+
+    #[test]
+    fn seconds_are_counted_in_ticks_at_the_fps() {
+        assert_eq!(Delay::Seconds(1.5).ticks(FPS), 15);
+        assert_eq!(Delay::Seconds(0.01).ticks(FPS), 1);
+        assert_eq!(Delay::Ticks(0).ticks(FPS), 1);
+    }
+
+    #[test]
+    fn starting_a_timer_replaces_the_owners_one_with_the_same_name() {
+        let (owner, other) = (EntityId::new(0, 0), EntityId::new(1, 0));
+        let mut timers = Timers::default();
+        timers.start(Some(owner), Timer::after("ready", Delay::Ticks(1)));
+        timers.start(Some(other), Timer::after("ready", Delay::Ticks(1)));
+        timers.start(Some(owner), Timer::after("ready", Delay::Ticks(2)));
+
+        assert_eq!(timers.tick(FPS), [(Some(other), "ready")]);
+        assert_eq!(timers.tick(FPS), [(Some(owner), "ready")]);
+    }
+
+    #[test]
+    fn cancelling_all_only_stops_the_owners_timers() {
+        let (owner, other) = (EntityId::new(0, 0), EntityId::new(1, 0));
+        let mut timers = Timers::default();
+        timers.start(Some(owner), Timer::every("a", Delay::Ticks(1)));
+        timers.start(Some(owner), Timer::every("b", Delay::Ticks(1)));
+        timers.start(Some(other), Timer::every("a", Delay::Ticks(1)));
+        timers.start(None, Timer::every("a", Delay::Ticks(1)));
+        timers.cancel_all(owner);
+
+        assert_eq!(timers.tick(FPS), [(Some(other), "a"), (None, "a")]);
+    }
+}
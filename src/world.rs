@@ -0,0 +1,554 @@
+use std::{
+    any::Any,
+    cell::{Ref, RefCell},
+    collections::HashMap,
+};
+
+use ratatui::layout::Rect;
+
+use crate::{
+    entity::{Collider, ColliderKind, Effect, Entity, EntityId, Vector},
+    event::Event,
+    pathfinding::NavGrid,
+    random::Rng,
+    spatial::{Bounds, SpatialGrid},
+    status::{StatusKind, Statuses},
+    timer::{Timer, Timers},
+    EntityIds, EntityState, X_SCALE, Y_SCALE,
+};
+
+/// Pixels added around query areas when looking up candidates in the spatial grid.
+const AREA_PADDING: f32 = 2.0;
+
+/// Half the side of the first square `World::nearest` searches, in pixels. It doubles
+/// until something matches.
+const NEAREST_START_RADIUS: f32 = 16.0;
+
+/// Another entity found by a world query.
+/// Positions are in pixels, the same units as `Vector`, with `y` growing upwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Seen {
+    pub id: EntityId,
+    /// Middle of the entity, from the bottom-left of the canvas.
+    pub center: (f32, f32),
+    /// Offset from the middle of the entity being updated to the middle of this one.
+    pub offset: (f32, f32),
+    pub distance: f32,
+    /// How far it moved last tick, e.g. to aim where it's heading.
+    pub velocity: (f32, f32),
+}
+
+/// The game world as seen by an entity while it updates.
+pub struct World<'a> {
+    id: EntityId,
+    center: (f32, f32),
+    collider: Collider,
+    statuses: Statuses,
+    bounds: Rect,
+    entity_states: &'a [RefCell<EntityState>],
+    lookup: &'a [usize],
+    grid: &'a SpatialGrid,
+    index: &'a QueryIndex,
+    ids: &'a mut EntityIds,
+    timers: &'a mut Timers,
+    queued: &'a mut Queued,
+    rng: &'a mut Rng,
+}
+
+impl<'a> World<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        entity_state: &EntityState,
+        bounds: Rect,
+        entity_states: &'a [RefCell<EntityState>],
+        lookup: &'a [usize],
+        grid: &'a SpatialGrid,
+        index: &'a QueryIndex,
+        ids: &'a mut EntityIds,
+        timers: &'a mut Timers,
+        queued: &'a mut Queued,
+        rng: &'a mut Rng,
+    ) -> Self {
+        Self {
+            id: entity_state.id,
+            center: entity_state.center(),
+            collider: entity_state.collider,
+            statuses: entity_state.statuses.clone(),
+            bounds,
+            entity_states,
+            lookup,
+            grid,
+            index,
+            ids,
+            timers,
+            queued,
+            rng,
+        }
+    }
+
+    /// Borrow another entity's state by its ID.
+    /// `None` for the entity being updated, since it's already borrowed.
+    fn state(&self, id: EntityId) -> Option<Ref<'a, EntityState>> {
+        if !self.ids.is_alive(id) {
+            return None;
+        }
+
+        let entity_state = self.entity_states.get(*self.lookup.get(id.index())?)?;
+        entity_state
+            .try_borrow()
+            .ok()
+            .filter(|entity_state| entity_state.id == id)
+    }
+
+    /// Every other entity that still exists, borrowed read-only.
+    /// With an area in pixels, only entities that might be in it are returned.
+    fn others(&self, area: Option<(f32, f32, f32, f32)>) -> Vec<Ref<'a, EntityState>> {
+        match area {
+            // the grid holds collision boxes, which are a little smaller than sprites
+            Some((left, bottom, right, top)) => self.borrow(self.grid.query(Bounds::new(
+                ((left - AREA_PADDING) * X_SCALE as f32).floor() as i32,
+                ((bottom - AREA_PADDING) * Y_SCALE as f32).floor() as i32,
+                ((right + AREA_PADDING) * X_SCALE as f32).ceil() as i32,
+                ((top + AREA_PADDING) * Y_SCALE as f32).ceil() as i32,
+            ))),
+            None => self.borrow(0..self.entity_states.len()),
+        }
+    }
+
+    /// The other entities at these indices that still exist, borrowed read-only.
+    fn borrow<T>(&self, indices: T) -> Vec<Ref<'a, EntityState>>
+    where
+        T: IntoIterator<Item = usize>,
+    {
+        let entity_states = self.entity_states;
+
+        indices
+            .into_iter()
+            // the entity being updated is already mutably borrowed, so it's skipped here
+            .filter_map(|index| entity_states[index].try_borrow().ok())
+            .filter(|entity_state| entity_state.entity.is_some())
+            .collect()
+    }
+
+    /// Indices of the entities with the given tag, as of the start of the tick.
+    fn tag_indices(&self, tag: &str) -> &'a [usize] {
+        self.index.tagged.get(tag).map_or(&[], Vec::as_slice)
+    }
+
+    fn see(&self, entity_state: &EntityState) -> Seen {
+        let center = to_pixels(entity_state.center());
+        let own_center = self.center();
+        let offset = (center.0 - own_center.0, center.1 - own_center.1);
+
+        Seen {
+            id: entity_state.id,
+            center,
+            offset,
+            distance: offset.0.hypot(offset.1),
+            velocity: (entity_state.velocity.x, entity_state.velocity.y),
+        }
+    }
+
+    /// Candidates matching `filter`, nearest first.
+    fn query<F>(&self, candidates: Vec<Ref<EntityState>>, filter: F) -> Vec<Seen>
+    where
+        F: Fn(&EntityState, &Seen) -> bool,
+    {
+        let mut seen: Vec<_> = candidates
+            .into_iter()
+            .filter_map(|entity_state| {
+                let seen = self.see(&entity_state);
+                filter(&entity_state, &seen).then_some(seen)
+            })
+            .collect();
+
+        // ties are broken by ID so queries are deterministic
+        seen.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.id.cmp(&b.id)));
+        seen
+    }
+
+    /// ID of the entity being updated.
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /// Whether the entity with this ID still exists, including entities spawned this tick.
+    /// Entities destroyed earlier this tick no longer count.
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        if id == self.id || self.queued.spawns.iter().any(|spawn| spawn.id == id) {
+            return true;
+        }
+
+        self.state(id)
+            .is_some_and(|entity_state| entity_state.entity.is_some())
+    }
+
+    /// Another entity as its concrete type, read-only, e.g. to check a target's health.
+    /// `None` if it's gone, isn't a `T`, or is the entity being updated.
+    pub fn get<T: Entity>(&self, id: EntityId) -> Option<Ref<'a, T>> {
+        Ref::filter_map(self.state(id)?, |entity_state| {
+            entity_state.entity.as_deref()?.downcast_ref::<T>()
+        })
+        .ok()
+    }
+
+    /// Whether the entity being updated has a status like `kind`, e.g. a slow of any
+    /// speed. Stunned entities aren't updated, so they never see their stun here.
+    pub fn has_status(&self, kind: StatusKind) -> bool {
+        self.statuses.has(kind)
+    }
+
+    /// Middle of the entity being updated, in pixels from the bottom-left of the canvas.
+    pub fn center(&self) -> (f32, f32) {
+        to_pixels(self.center)
+    }
+
+    /// Middle of another entity, in pixels from the bottom-left of the canvas.
+    pub fn position(&self, id: EntityId) -> Option<(f32, f32)> {
+        if id == self.id {
+            return Some(self.center());
+        }
+
+        self.state(id)
+            .filter(|entity_state| entity_state.entity.is_some())
+            .map(|entity_state| to_pixels(entity_state.center()))
+    }
+
+    /// The nearest other entity matching `filter`, e.g. `|entity| entity.is::<Player>()`.
+    pub fn nearest<F>(&self, filter: F) -> Option<Seen>
+    where
+        F: Fn(&dyn Entity) -> bool,
+    {
+        self.search_outwards(None, |entity_state| matches(entity_state, &filter))
+    }
+
+    /// The nearest other entity with the given tag.
+    pub fn nearest_tagged(&self, tag: &str) -> Option<Seen> {
+        self.search_outwards(Some(self.tag_indices(tag)), |entity_state| {
+            matches(entity_state, |entity| entity.has_tag(tag))
+        })
+    }
+
+    /// The nearest other entity matching `filter`, searching ever bigger squares around
+    /// the entity so only nearby entities are checked when something close matches.
+    /// With `tagged`, only those indices can match, and they're checked straight away
+    /// once there are fewer of them than entities in the square.
+    fn search_outwards<F>(&self, tagged: Option<&[usize]>, filter: F) -> Option<Seen>
+    where
+        F: Fn(&EntityState) -> bool,
+    {
+        let (x, y) = self.center();
+        let (width, height) = to_pixels((self.bounds.right() as f32, self.bounds.bottom() as f32));
+
+        // anything within the radius is inside the square, so the nearest match
+        // within it is the nearest anywhere
+        let mut radius = NEAREST_START_RADIUS;
+        while radius < width.max(height) {
+            let candidates = self.others(Some((x - radius, y - radius, x + radius, y + radius)));
+            if tagged.is_some_and(|tagged| tagged.len() <= candidates.len()) {
+                break;
+            }
+
+            let nearest = self
+                .query(candidates, |entity_state, seen| {
+                    seen.distance <= radius && filter(entity_state)
+                })
+                .into_iter()
+                .next();
+            if nearest.is_some() {
+                return nearest;
+            }
+
+            radius *= 2.0;
+        }
+
+        let candidates = match tagged {
+            Some(tagged) => self.borrow(tagged.iter().copied()),
+            None => self.others(None),
+        };
+        self.query(candidates, |entity_state, _| filter(entity_state))
+            .into_iter()
+            .next()
+    }
+
+    /// Every other entity with the given tag, nearest first.
+    pub fn tagged(&self, tag: &str) -> Vec<Seen> {
+        let candidates = self.borrow(self.tag_indices(tag).iter().copied());
+        self.query(candidates, |entity_state, _| {
+            matches(entity_state, |entity| entity.has_tag(tag))
+        })
+    }
+
+    /// Other entities whose middle is within `radius` pixels, nearest first.
+    pub fn within_radius<F>(&self, radius: f32, filter: F) -> Vec<Seen>
+    where
+        F: Fn(&dyn Entity) -> bool,
+    {
+        let (x, y) = self.center();
+        let area = (x - radius, y - radius, x + radius, y + radius);
+
+        self.query(self.others(Some(area)), |entity_state, seen| {
+            seen.distance <= radius && matches(entity_state, &filter)
+        })
+    }
+
+    /// Other entities whose middle is inside the rectangle, nearest first.
+    /// The rectangle is in pixels, with its bottom-left corner at `x`/`y`.
+    pub fn within_rect<F>(&self, x: f32, y: f32, width: f32, height: f32, filter: F) -> Vec<Seen>
+    where
+        F: Fn(&dyn Entity) -> bool,
+    {
+        let area = (x, y, x + width, y + height);
+        self.query(self.others(Some(area)), |entity_state, seen| {
+            let (center_x, center_y) = seen.center;
+
+            (x..=x + width).contains(&center_x)
+                && (y..=y + height).contains(&center_y)
+                && matches(entity_state, &filter)
+        })
+    }
+
+    /// The first other entity hit by a ray from the middle of the entity being updated,
+    /// travelling up to `max_distance` pixels along `direction`.
+    pub fn raycast<F>(&self, direction: Vector, max_distance: f32, filter: F) -> Option<Seen>
+    where
+        F: Fn(&dyn Entity) -> bool,
+    {
+        if direction.length() == 0.0 {
+            return None;
+        }
+        let step = direction.normalized();
+        let step = (step.x, step.y);
+        let (start_x, start_y) = self.center();
+        let (end_x, end_y) = (
+            start_x + step.0 * max_distance,
+            start_y + step.1 * max_distance,
+        );
+        let area = (
+            start_x.min(end_x),
+            start_y.min(end_y),
+            start_x.max(end_x),
+            start_y.max(end_y),
+        );
+        let candidates: Vec<_> = self
+            .others(Some(area))
+            .into_iter()
+            .filter(|entity_state| matches(entity_state, &filter))
+            .collect();
+
+        // march along the ray half a pixel at a time
+        let mut travelled = 0.0;
+        while travelled <= max_distance {
+            let point = (start_x + step.0 * travelled, start_y + step.1 * travelled);
+            let hit = candidates
+                .iter()
+                .filter(|entity_state| contains(entity_state, point))
+                .min_by_key(|entity_state| entity_state.id);
+            if let Some(entity_state) = hit {
+                return Some(self.see(entity_state));
+            }
+
+            travelled += 0.5;
+        }
+
+        None
+    }
+
+    /// Grid over the bounds with every cell blocked that overlaps a solid the entity
+    /// being updated can't move through, for finding paths around them.
+    /// `cell_size` is in pixels.
+    pub fn nav_grid(&self, cell_size: f32) -> NavGrid {
+        let (width, height) = to_pixels((self.bounds.right() as f32, self.bounds.bottom() as f32));
+        let mut grid = NavGrid::new(width, height, cell_size);
+
+        for entity_state in self.borrow(self.index.solids.iter().copied()) {
+            if !self.collider.is_blocked_by(&entity_state.collider) {
+                continue;
+            }
+
+            let Bounds {
+                left,
+                bottom,
+                right,
+                top,
+            } = entity_state.bounding_box();
+            let (x, y) = to_pixels((left as f32, bottom as f32));
+            let (width, height) = to_pixels(((right - left) as f32, (top - bottom) as f32));
+            grid.block_rect(x, y, width, height);
+        }
+
+        grid
+    }
+
+    /// Spawn a new entity once every entity has updated this tick.
+    /// Its middle is placed `offset` away from the middle of the entity spawning it.
+    /// If that's out of bounds, it's moved back within them, or dropped if its
+    /// boundary is `Boundary::Destroy` or its sprite can't fit.
+    pub fn spawn(&mut self, entity: Box<dyn Entity>, offset: Vector) -> EntityId {
+        let id = self.ids.allocate();
+
+        self.queued.spawns.push(Spawn {
+            id,
+            entity,
+            center: (
+                self.center.0 + offset.x * X_SCALE as f32,
+                self.center.1 + offset.y * Y_SCALE as f32,
+            ),
+        });
+
+        id
+    }
+
+    /// Send an effect to another entity, once every entity has updated this tick.
+    /// Statuses sent this way are tracked by the engine.
+    pub fn send(&mut self, id: EntityId, effect: Effect) {
+        self.queued.effects.push((id, effect));
+    }
+
+    /// Send an effect to every entity found by a query, e.g. `world.within_radius(..)`.
+    pub fn broadcast<T>(&mut self, targets: T, effect: Effect)
+    where
+        T: IntoIterator<Item = Seen>,
+    {
+        for seen in targets {
+            self.send(seen.id, effect.clone());
+        }
+    }
+
+    /// Send an effect to every other entity with the given tag.
+    pub fn broadcast_tagged(&mut self, tag: &str, effect: Effect) {
+        let targets = self.tagged(tag);
+        self.broadcast(targets, effect);
+    }
+
+    /// Start a timer for the entity being updated, replacing any it already has with
+    /// the same name. Its `on_timer` is called when it goes off.
+    pub fn start_timer(&mut self, timer: Timer) {
+        self.timers.start(Some(self.id), timer);
+    }
+
+    pub fn cancel_timer(&mut self, name: &str) {
+        self.timers.cancel(Some(self.id), name);
+    }
+
+    /// Whether the entity being updated has a timer with this name still running,
+    /// e.g. a cooldown.
+    pub fn has_timer(&self, name: &str) -> bool {
+        self.timers.is_running(Some(self.id), name)
+    }
+
+    /// The game's random numbers, the same every run with the same seed.
+    pub fn rng(&mut self) -> &mut Rng {
+        self.rng
+    }
+
+    /// Publish an event for anything that cares about it, at the end of the tick.
+    pub fn publish<T: Any>(&mut self, event: T) {
+        self.queued.events.push(Event::new(event));
+    }
+}
+
+#[cfg(test)]
+impl World<'_> {
+    /// Run `f` with the world of a lone entity at the origin, for testing code that
+    /// needs a world but not what's in it.
+    pub(crate) fn with_empty<R>(f: impl FnOnce(&mut World) -> R) -> R {
+        use std::rc::Rc;
+
+        use crate::{entity::Rotation, entity::Sprite, Position};
+
+        let mut ids = EntityIds::default();
+        let entity_state = EntityState {
+            id: ids.allocate(),
+            pos: Some(Position { x: 0.0, y: 0.0 }),
+            rot: Rotation::Zero,
+            sprite: Rc::new(Sprite::filled(1, 1, (255, 255, 255))),
+            collider: Collider::default(),
+            boundary: Default::default(),
+            statuses: Statuses::default(),
+            velocity: Vector::default(),
+            entity: None,
+        };
+        let (grid, index) = (SpatialGrid::new(1), QueryIndex::default());
+        let (mut timers, mut queued, mut rng) = (Timers::default(), Queued::default(), Rng::new(0));
+
+        f(&mut World::new(
+            &entity_state,
+            Rect::new(0, 0, 1, 1),
+            &[],
+            &[],
+            &grid,
+            &index,
+            &mut ids,
+            &mut timers,
+            &mut queued,
+            &mut rng,
+        ))
+    }
+}
+
+fn to_pixels((x, y): (f32, f32)) -> (f32, f32) {
+    (x / X_SCALE as f32, y / Y_SCALE as f32)
+}
+
+fn matches<F>(entity_state: &EntityState, filter: F) -> bool
+where
+    F: Fn(&dyn Entity) -> bool,
+{
+    entity_state.entity.as_deref().is_some_and(filter)
+}
+
+/// Whether a point in pixels lies on the entity's sprite.
+fn contains(entity_state: &EntityState, (x, y): (f32, f32)) -> bool {
+    let pos = entity_state.pos.expect("entity has a position");
+    let (left, bottom) = to_pixels((pos.x, pos.y));
+
+    (left..left + entity_state.sprite.width() as f32).contains(&x)
+        && (bottom..bottom + entity_state.sprite.height() as f32).contains(&y)
+}
+
+/// Which entities world queries look through for tags and solids, so they don't
+/// have to check every entity. Rebuilt every tick along with the spatial grid.
+#[derive(Debug, Default)]
+pub(crate) struct QueryIndex {
+    tagged: HashMap<&'static str, Vec<usize>>,
+    solids: Vec<usize>,
+}
+
+impl QueryIndex {
+    pub(crate) fn clear(&mut self) {
+        // keep the buckets around, like the grid
+        for indices in self.tagged.values_mut() {
+            indices.clear();
+        }
+        self.solids.clear();
+    }
+
+    pub(crate) fn insert(&mut self, index: usize, entity_state: &EntityState) {
+        let Some(entity) = entity_state.entity.as_deref() else {
+            return;
+        };
+
+        for tag in entity.tags() {
+            self.tagged.entry(tag).or_default().push(index);
+        }
+        if entity_state.collider.kind == ColliderKind::Solid {
+            self.solids.push(index);
+        }
+    }
+}
+
+/// An entity waiting to be added at the end of the tick.
+pub(crate) struct Spawn {
+    pub(crate) id: EntityId,
+    pub(crate) entity: Box<dyn Entity>,
+    pub(crate) center: (f32, f32),
+}
+
+/// Changes requested by entities while they update, applied once they all have.
+#[derive(Default)]
+pub(crate) struct Queued {
+    pub(crate) spawns: Vec<Spawn>,
+    pub(crate) effects: Vec<(EntityId, Effect)>,
+    pub(crate) events: Vec<Event>,
+}